@@ -1,6 +1,6 @@
 use colored::*;
-use log::debug;
-use rand::Rng;
+use log::{debug, error};
+use rand::{seq::SliceRandom, Rng};
 use std::{
     error::Error,
     fmt,
@@ -11,12 +11,24 @@ use std::{
 };
 
 use crate::{
-    input::{AppSignal, MIDI},
+    input::{AppSignal, Midi},
     print,
     theory::{
-        chord::{Chord, ChordType, Inversion},
+        analysis::{self, Pivot},
+        cadence::Cadence,
+        chord::{Chord, ChordType, Voicing, VoicingType},
+        dyad::{Dyad, IntervalStats},
+        error::TheoryError,
+        fretboard::{self, Tuning},
         key::{Key, KeyType},
-        modulation::{DeTour, Modulation},
+        modulation::{Chain, DeTour, Modulation},
+        neo_riemannian,
+        pitch::Pitch,
+        scale::Scale,
+        sequence::{self, Changes, Coverage},
+        template::Template,
+        tone::Tone,
+        voice_leading,
     },
 };
 
@@ -39,6 +51,42 @@ impl fmt::Display for Difficulty {
     }
 }
 
+/// Where the chords of a session come from.
+#[derive(Debug, Clone)]
+pub enum Drill {
+    Functional, // functional harmony: detours and modulations
+    Tonnetz,    // neo-Riemannian walk, non-functional chromatic triads
+    Scale,      // scales up and back down, one note at a time
+    Cadence,    // named cadences in random keys and modes
+    Template,   // standard progressions, a chorus at a time in random keys
+    Interval,   // two notes an interval apart, named from the root
+}
+
+impl fmt::Display for Drill {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Drill::Functional => {
+                write!(f, "{}", "functional".cyan().bold())
+            }
+            Drill::Tonnetz => {
+                write!(f, "{}", "tonnetz".red().bold())
+            }
+            Drill::Scale => {
+                write!(f, "{}", "scale".yellow().bold())
+            }
+            Drill::Cadence => {
+                write!(f, "{}", "cadence".green().bold())
+            }
+            Drill::Template => {
+                write!(f, "{}", "template".purple().bold())
+            }
+            Drill::Interval => {
+                write!(f, "{}", "interval".blue().bold())
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum GamePhase {
     Intro,
@@ -46,15 +94,26 @@ pub(crate) enum GamePhase {
     Ready,
     Playing,
     MeasureStart { measure: i32 },
-    WaitingForInput { target: Chord },
-    Matched { chord: Chord },
+    WaitingForInput { target: Chord, numeral: String, scale: Scale },
+    Matched { chord: Chord, numeral: String, scale: Scale },
+    PlayingScale {
+        scale: Scale,
+        expected: Vec<Pitch>,
+        step: usize,
+        miss: Option<String>,
+    },
+    PlayingInterval {
+        dyad: Dyad,
+        answer: Vec<Pitch>, // the two notes spelled, once they are played
+        miss: Option<String>,
+    },
     Score,
     MeasureTimeout,
     GameTimeout,
     Summary { duration_secs: u64 },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Status {
     ss_idx: usize,
     pub(crate) chords: Vec<Chord>,
@@ -68,6 +127,12 @@ struct AppEnv {
     sleep_time: u64,
     total_iteration: i32,
     modulation_threshold: i32,
+    voice_leading: bool,
+    voice_range: Option<(u8, u8)>, // midi notes, None for the whole instrument
+    chain: Chain,                  // extended dominants, depth 0 to leave them out
+    walk: usize,                   // transformations per measure on the tonnetz
+    scale_gap: u64,                // ms allowed between two notes of a scale
+    tuning: Option<Tuning>,        // strings to finger the chords on, None off the guitar
 }
 
 impl AppEnv {
@@ -78,12 +143,30 @@ impl AppEnv {
                 sleep_time: 30,
                 total_iteration: 100,
                 modulation_threshold: 4,
+                voice_leading: false,
+                voice_range: Some((48, 79)), // C3 - G5
+                chain: Chain {
+                    depth: 4,
+                    tritone_subs: true,
+                },
+                walk: 3,
+                scale_gap: 1500,
+                tuning: None,
             },
             Difficulty::Guitar => AppEnv {
                 total_time: 120,
                 sleep_time: 30,
                 total_iteration: 100,
                 modulation_threshold: 4,
+                voice_leading: false,
+                voice_range: Some((40, 76)), // E2 - E5, up to the 12th fret
+                chain: Chain {
+                    depth: 3,
+                    tritone_subs: false,
+                },
+                walk: 2,
+                scale_gap: 2000,
+                tuning: Some(Tuning::Standard),
             },
         }
     }
@@ -94,18 +177,31 @@ pub struct App {
     input_rx: Receiver<AppSignal>,
 
     pub(crate) difficulty: Difficulty,
+    pub(crate) drill: Drill,
+    pub(crate) voicing_type: VoicingType,
     env: AppEnv,
     pub(crate) score: i32,
-    ss: Vec<i8>, // std seq
+    ss: Vec<i8>,      // std seq
+    changes: Changes, // the sequence covers, read in the key of the next chords
 
     pub(crate) prevous_key: Key,
     pub(crate) current: Status,
     pub(crate) modulation: Modulation,
+    pub(crate) pivot: Option<Pivot>, // explains a shared chord modulation
+    pub(crate) scale: Option<Scale>, // to practice, on the scale drill
+    pub(crate) cadence: Option<Cadence>, // to name, on the cadence drill
+    pub(crate) template: Option<Template>, // in its chorus, on the template drill
+    pub(crate) phrase: usize,        // of the template, the next one to play
+    pub(crate) dyad: Option<Dyad>,   // to play, on the interval drill
+    pub(crate) interval_stats: IntervalStats,
     pub(crate) next: Status,
+    pub(crate) error: Option<TheoryError>, // last failed step, shown on screen
+    pub(crate) midi_error: Option<String>, // why MIDI did not connect, shown all game
 
     pub(crate) phase: GamePhase,
     pub(crate) measure_num: i32,
     pub(crate) start_time: Option<SystemTime>,
+    pub(crate) played: Vec<Pitch>,
 }
 
 impl App {
@@ -116,6 +212,14 @@ impl App {
         Ok(())
     }
 
+    pub(crate) fn voice_leading(&self) -> bool {
+        self.env.voice_leading
+    }
+
+    pub(crate) fn tuning(&self) -> Option<&Tuning> {
+        self.env.tuning.as_ref()
+    }
+
     pub(crate) fn elapsed_secs(&self) -> u64 {
         self.start_time
             .and_then(|s| SystemTime::now().duration_since(s).ok())
@@ -154,8 +258,118 @@ impl App {
         difficulty
     }
 
+    fn select_tuning(input_rx: &Receiver<AppSignal>) -> Tuning {
+        let all = Tuning::all_vec();
+        let mut idx = 0;
+        print::select_tuning(&all[idx]);
+        loop {
+            thread::sleep(Duration::from_millis(500));
+            match input_rx.try_recv() {
+                Ok(AppSignal::Tuning) => {
+                    idx = (idx + 1) % all.len();
+                    print::select_tuning(&all[idx]);
+                }
+                Ok(AppSignal::Next) => {
+                    print::tuning_selected(&all[idx]);
+                    return all[idx].clone();
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    fn select_drill(input_rx: &Receiver<AppSignal>) -> Drill {
+        loop {
+            thread::sleep(Duration::from_millis(500));
+            match input_rx.try_recv() {
+                Ok(AppSignal::Functional) => {
+                    print::functional_selected();
+                    return Drill::Functional;
+                }
+                Ok(AppSignal::Tonnetz) => {
+                    print::tonnetz_selected();
+                    return Drill::Tonnetz;
+                }
+                Ok(AppSignal::Scale) => {
+                    print::scale_selected();
+                    return Drill::Scale;
+                }
+                Ok(AppSignal::Cadence) => {
+                    print::cadence_selected();
+                    return Drill::Cadence;
+                }
+                Ok(AppSignal::Template) => {
+                    print::template_selected();
+                    return Drill::Template;
+                }
+                Ok(AppSignal::Interval) => {
+                    print::interval_selected();
+                    return Drill::Interval;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    fn select_voicing(input_rx: &Receiver<AppSignal>) -> VoicingType {
+        loop {
+            thread::sleep(Duration::from_millis(500));
+            let voicing_type = match input_rx.try_recv() {
+                Ok(AppSignal::Close) => VoicingType::Close,
+                Ok(AppSignal::Drop2) => VoicingType::Drop2,
+                Ok(AppSignal::Drop3) => VoicingType::Drop3,
+                Ok(AppSignal::Drop24) => VoicingType::Drop24,
+                Ok(AppSignal::Spread) => VoicingType::Spread,
+                Ok(AppSignal::RootlessA) => VoicingType::RootlessA,
+                Ok(AppSignal::RootlessB) => VoicingType::RootlessB,
+                Ok(AppSignal::UpperStructure) => VoicingType::UpperStructure,
+                _ => continue,
+            };
+            print::voicing_selected(&voicing_type);
+            return voicing_type;
+        }
+    }
+
+    fn select_voice_leading(input_rx: &Receiver<AppSignal>) -> bool {
+        let mut voice_leading = false;
+        print::select_voice_leading(voice_leading);
+        loop {
+            thread::sleep(Duration::from_millis(500));
+            match input_rx.try_recv() {
+                Ok(AppSignal::VoiceLeading) => {
+                    voice_leading = !voice_leading;
+                    print::select_voice_leading(voice_leading);
+                }
+                Ok(AppSignal::Next) => {
+                    print::voice_leading_selected(voice_leading);
+                    return voice_leading;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    fn select_changes(input_rx: &Receiver<AppSignal>) -> Changes {
+        let all = Changes::all_vec();
+        let mut idx = 0;
+        print::select_changes(&all[idx]);
+        loop {
+            thread::sleep(Duration::from_millis(500));
+            match input_rx.try_recv() {
+                Ok(AppSignal::Changes) => {
+                    idx = (idx + 1) % all.len();
+                    print::select_changes(&all[idx]);
+                }
+                Ok(AppSignal::Next) => {
+                    return all[idx].clone();
+                }
+                _ => continue,
+            }
+        }
+    }
+
     fn init_midi(input_rx: &Receiver<AppSignal>, msg_tx: Sender<u8>) -> Result<(), Box<dyn Error>> {
-        let midi = MIDI::new()?;
+        let midi = Midi::new()?;
         let mut conn_out = midi.output.connect(&midi.output_port, "")?;
         let _conn_in = midi.input.connect(
             &midi.input_port,
@@ -169,45 +383,103 @@ impl App {
     }
 
     pub fn new(input_rx: Receiver<AppSignal>, msg_tx: Sender<u8>) -> Result<App, Box<dyn Error>> {
-        Self::init_midi(&input_rx, msg_tx);
+        let midi = Self::init_midi(&input_rx, msg_tx);
 
         print::select_difficulty();
         let difficulty = Self::select_difficulty(&input_rx);
-        let env = AppEnv::new(&difficulty);
-
-        let ss = vec![
-            1, 3, 1, 4, 2, 5, 6, 3, 7, 1, 4, 5, 3, 2, 4, 7, 6, 5, 6, 1, 7, 6, 2, 4, 5, 1, 5, 3, 6,
-            7, 3, 4, 2, 1, 6, 2, 7, 3, 5, 1,
-        ];
+        let tuning = match difficulty {
+            Difficulty::Piano => None,
+            Difficulty::Guitar => Some(Self::select_tuning(&input_rx)),
+        };
+        print::select_drill();
+        let drill = Self::select_drill(&input_rx);
+        print::select_voicing();
+        let voicing_type = Self::select_voicing(&input_rx);
+        let voice_leading = Self::select_voice_leading(&input_rx);
+        // only the functional drill walks the degree sequence
+        let changes = match drill {
+            Drill::Functional => Self::select_changes(&input_rx),
+            _ => Changes::All,
+        };
+        let mut env = AppEnv::new(&difficulty);
+        env.voice_leading = voice_leading;
+        if let Some(tuning) = tuning {
+            env.voice_range = Some(tuning.range());
+            env.tuning = Some(tuning);
+        }
 
         let current_key = Key::sample(difficulty.clone())?;
         let prevous_key = current_key.clone();
+
+        let (ss, coverage) = Self::sequence(&changes, &current_key)?;
+        if let Drill::Functional = drill {
+            print::changes_selected(&changes, &coverage);
+        }
         // current_key.log_all_chords();
 
-        let mut current_ss_idx = rand::thread_rng().gen_range(0..40);
-        let current_chord = current_key.gen_chord(ss[current_ss_idx], difficulty.clone())?;
+        let current_ss_idx = rand::thread_rng().gen_range(0..ss.len());
+        let current_chord = match drill {
+            Drill::Functional => {
+                current_key.gen_extended_chord(ss[current_ss_idx], difficulty.clone())?
+            }
+            Drill::Cadence | Drill::Template => current_key.gen_chord(1, difficulty.clone())?,
+            // the walk starts from the tonic triad, which the drills without
+            // chords keep as a placeholder
+            Drill::Tonnetz | Drill::Scale | Drill::Interval => Chord::new(
+                current_key.tonic.clone(),
+                ChordType::Major,
+                Voicing::sample(difficulty.clone())?,
+            )?,
+        }
+        .restyled(voicing_type.clone())?;
         let current_key_iteration = 1;
 
         let modulation = Modulation::SameKey;
-        let mut next_ss_idx = current_ss_idx + 1;
-        if next_ss_idx > (ss.len() - 1) {
-            next_ss_idx %= (ss.len() - 1);
-        }
-        let next_key = current_key.clone();
+        let next_ss_idx = (current_ss_idx + 1) % ss.len();
+        let mut next_key = current_key.clone();
         let next_key_iteration = current_key_iteration + 1;
+        let mut cadence = None;
+        let mut template = None;
 
-        let detour: DeTour = DeTour::sample(difficulty.clone())?;
-        let next_chords = detour.build_chords(
-            current_key.gen_chord(ss[next_ss_idx], difficulty.clone())?,
-            difficulty.clone(),
-        )?;
+        let next_chords = match drill {
+            Drill::Scale | Drill::Interval => Vec::new(),
+            Drill::Functional => {
+                let detour: DeTour = DeTour::sample(difficulty.clone(), &env.chain)?;
+                detour.build_chords(
+                    current_key.gen_extended_chord(ss[next_ss_idx], difficulty.clone())?,
+                    difficulty.clone(),
+                )?
+            }
+            Drill::Tonnetz => {
+                let chords = neo_riemannian::walk(&current_chord, env.walk, difficulty.clone())?;
+                next_key = neo_riemannian::home_key(&chords[0]);
+                chords
+            }
+            Drill::Cadence => {
+                let sampled = Cadence::sample(difficulty.clone())?;
+                next_key = sampled.sample_key(difficulty.clone())?;
+                let chords = sampled.build_chords(&next_key, difficulty.clone())?;
+                cadence = Some(sampled);
+                chords
+            }
+            Drill::Template => {
+                let sampled = Template::sample(difficulty.clone())?;
+                next_key = sampled.sample_key(difficulty.clone())?;
+                let chords = sampled.build_chords(0, &next_key, difficulty.clone())?;
+                template = Some(sampled);
+                chords
+            }
+        };
 
-        Ok(App {
+        let mut app = App {
             input_rx,
             difficulty,
+            drill,
+            voicing_type,
             env,
             score: 0,
             ss,
+            changes,
             prevous_key,
 
             current: Status {
@@ -217,17 +489,33 @@ impl App {
                 key_iteration: current_key_iteration,
             },
             modulation,
+            pivot: None,
+            scale: None,
+            cadence,
+            template,
+            phrase: 1,
+            dyad: None,
+            interval_stats: IntervalStats::default(),
             next: Status {
                 ss_idx: next_ss_idx,
                 chords: next_chords,
                 key: next_key,
                 key_iteration: next_key_iteration,
             },
+            error: None,
+            midi_error: midi.err().map(|e| {
+                error!("App::new(): {}", e);
+                e.to_string()
+            }),
 
             phase: GamePhase::SelectDifficulty,
             measure_num: 0,
             start_time: None,
-        })
+            played: Vec::new(),
+        };
+        app.voice_chords();
+        app.fret_chords();
+        Ok(app)
     }
 
     fn key_vec_thread(msg_rx: Receiver<u8>) -> Receiver<Vec<u8>> {
@@ -297,22 +585,247 @@ impl App {
         let game_timeout_rx = Self::game_timeout_thread(start, self.env.total_time);
 
         'measure: for i in 1..self.env.total_iteration {
-            self.next();
+            if self.next().is_none() {
+                break 'measure;
+            }
+            // the scale and interval drills play no chords
+            let chords_drilled = !matches!(self.drill, Drill::Scale | Drill::Interval);
+            if chords_drilled {
+                if self.env.voice_leading {
+                    self.lead_voices();
+                } else {
+                    self.voice_chords();
+                }
+                self.fret_chords();
+            }
             self.measure_num = i;
             self.phase = GamePhase::MeasureStart { measure: i };
             self.render(terminal)?;
 
-            let mut chords_unmatched: Vec<Chord> =
-                self.next.chords.clone().into_iter().rev().collect();
-            chords_unmatched.remove(chords_unmatched.len() - 1);
-            chords_unmatched.insert(0, self.current.chords[0].clone());
+            let mut chords_unmatched = Vec::new();
+            let mut numerals_unmatched = Vec::new();
+            let mut scales_unmatched = Vec::new();
+            if chords_drilled {
+                let playing: Vec<Chord> = self.next.chords.clone().into_iter().rev().collect();
+                numerals_unmatched = self.labels(&playing);
+                scales_unmatched = self.chord_scales(&playing)?;
+                chords_unmatched = playing;
+                chords_unmatched.remove(chords_unmatched.len() - 1);
+                numerals_unmatched.remove(numerals_unmatched.len() - 1);
+                scales_unmatched.remove(scales_unmatched.len() - 1);
+                chords_unmatched.insert(0, self.current.chords[0].clone());
+            }
 
             let timeout_rx = Self::measure_timeout_thread(self.env.sleep_time);
-            while chords_unmatched.len() > 0 {
+            if let (Drill::Scale, Some(scale)) = (&self.drill, self.scale.clone()) {
+                // the tonic may be played in any octave, every next note has to be
+                // the next degree in that register and come in time
+                let floor = self.env.voice_range.map(|(low, _)| low).unwrap_or(48);
+                let mut expected = scale.up_and_down(floor);
+                let mut step = 0;
+                let mut held: Vec<u8> = Vec::new();
+                let mut first_note = SystemTime::now();
+                let mut last_note = SystemTime::now();
+                self.phase = GamePhase::PlayingScale {
+                    scale: scale.clone(),
+                    expected: expected.clone(),
+                    step,
+                    miss: None,
+                };
+                self.render(terminal)?;
+
+                'play_scale: loop {
+                    thread::sleep(Duration::from_millis(10));
+
+                    if let Ok(signal) = timeout_rx.try_recv() {
+                        self.phase = GamePhase::MeasureTimeout;
+                        self.render(terminal)?;
+                        continue 'measure;
+                    }
+
+                    if let Ok(signal) = game_timeout_rx.try_recv() {
+                        self.phase = GamePhase::GameTimeout;
+                        self.render(terminal)?;
+                        break 'measure;
+                    }
+
+                    if let Ok(signal) = self.input_rx.try_recv() {
+                        if let AppSignal::Quit = signal {
+                            break 'measure;
+                        }
+                        if let AppSignal::Next = signal {
+                            self.phase = GamePhase::Score;
+                            self.render(terminal)?;
+                            continue 'measure;
+                        }
+                    }
+
+                    let key_vec = match vec_rx.try_recv() {
+                        Ok(key_vec) => key_vec,
+                        Err(e) => {
+                            continue;
+                        }
+                    };
+                    let pressed: Vec<u8> = key_vec
+                        .iter()
+                        .filter(|e| !held.contains(e))
+                        .cloned()
+                        .collect();
+                    held = key_vec;
+                    self.played = held.iter().cloned().map(Pitch::from_midi).collect();
+
+                    let mut miss = None;
+                    for note in pressed.into_iter() {
+                        // notes pressed together with the last degree are not counted
+                        if step == expected.len() {
+                            break;
+                        }
+                        let now = SystemTime::now();
+                        let gap = now.duration_since(last_note)?;
+                        last_note = now;
+                        let in_time = gap <= Duration::from_millis(self.env.scale_gap);
+                        if step > 0 && in_time && note == expected[step].midi {
+                            step += 1;
+                            continue;
+                        }
+                        if step > 0 {
+                            miss = Some(match in_time {
+                                true => format!("wrong note, {} expected", expected[step]),
+                                false => "too slow, start again".to_string(),
+                            });
+                        }
+                        // anything else starts over, from this note if it is the tonic
+                        step = 0;
+                        if Pitch::class_of(note) == expected[0].class() {
+                            expected = scale.up_and_down(note);
+                            first_note = now;
+                            step = 1;
+                        }
+                    }
+
+                    if step == expected.len() {
+                        let duration = last_note.duration_since(first_note)?;
+                        let gap = duration.as_millis() as u64 / (expected.len() as u64 - 1);
+                        let tenths = self.env.scale_gap.saturating_sub(gap) / 100;
+                        self.score += tenths.pow(3) as i32;
+                        self.phase = GamePhase::PlayingScale {
+                            scale,
+                            expected,
+                            step,
+                            miss: None,
+                        };
+                        self.render(terminal)?;
+                        self.phase = GamePhase::Score;
+                        self.render(terminal)?;
+                        continue 'measure;
+                    }
+                    self.phase = GamePhase::PlayingScale {
+                        scale: scale.clone(),
+                        expected: expected.clone(),
+                        step,
+                        miss,
+                    };
+                    self.render(terminal)?;
+                }
+            }
+
+            if let (Drill::Interval, Some(dyad)) = (&self.drill, self.dyad.clone()) {
+                // the root may be played in any octave, the other note has to be
+                // the interval away from it in that register
+                let asked = SystemTime::now();
+                self.phase = GamePhase::PlayingInterval {
+                    dyad: dyad.clone(),
+                    answer: Vec::new(),
+                    miss: None,
+                };
+                self.render(terminal)?;
+
+                // one result per question: a hit if any attempt is right, else one miss
+                let mut missed = false;
+                'play_interval: loop {
+                    thread::sleep(Duration::from_millis(10));
+
+                    if let Ok(signal) = timeout_rx.try_recv() {
+                        self.interval_stats.miss(&dyad);
+                        self.phase = GamePhase::MeasureTimeout;
+                        self.render(terminal)?;
+                        continue 'measure;
+                    }
+
+                    if let Ok(signal) = game_timeout_rx.try_recv() {
+                        if missed {
+                            self.interval_stats.miss(&dyad);
+                        }
+                        self.phase = GamePhase::GameTimeout;
+                        self.render(terminal)?;
+                        break 'measure;
+                    }
+
+                    if let Ok(signal) = self.input_rx.try_recv() {
+                        if let AppSignal::Quit = signal {
+                            if missed {
+                                self.interval_stats.miss(&dyad);
+                            }
+                            break 'measure;
+                        }
+                        if let AppSignal::Next = signal {
+                            self.interval_stats.miss(&dyad);
+                            self.phase = GamePhase::Score;
+                            self.render(terminal)?;
+                            continue 'measure;
+                        }
+                    }
+
+                    let key_vec = match vec_rx.try_recv() {
+                        Ok(key_vec) => key_vec,
+                        Err(e) => {
+                            continue;
+                        }
+                    };
+                    self.played = key_vec.into_iter().map(Pitch::from_midi).collect();
+                    // judged once both notes are down
+                    let miss = match (self.played.len(), dyad.matches(&self.played)) {
+                        (2, Some(answer)) => {
+                            let duration = SystemTime::now().duration_since(asked)?;
+                            self.interval_stats.hit(&dyad, duration);
+                            if duration <= Duration::from_secs(8) {
+                                let secs = 8 - duration.as_secs();
+                                self.score += secs.pow(4) as i32;
+                            }
+                            self.phase = GamePhase::PlayingInterval {
+                                dyad,
+                                answer,
+                                miss: None,
+                            };
+                            self.render(terminal)?;
+                            self.phase = GamePhase::Score;
+                            self.render(terminal)?;
+                            continue 'measure;
+                        }
+                        (2, None) => {
+                            missed = true;
+                            Some(dyad.miss(&self.played))
+                        }
+                        _ => None,
+                    };
+                    self.phase = GamePhase::PlayingInterval {
+                        dyad: dyad.clone(),
+                        answer: Vec::new(),
+                        miss,
+                    };
+                    self.render(terminal)?;
+                }
+            }
+
+            while !chords_unmatched.is_empty() {
                 let target_chord = chords_unmatched.remove(0);
-                let target_key_vec: Vec<u8> =
-                    target_chord.tones.iter().map(|e| e.idx as u8).collect();
-                self.phase = GamePhase::WaitingForInput { target: target_chord.clone() };
+                let numeral = numerals_unmatched.remove(0);
+                let scale = scales_unmatched.remove(0);
+                self.phase = GamePhase::WaitingForInput {
+                    target: target_chord.clone(),
+                    numeral: numeral.clone(),
+                    scale: scale.clone(),
+                };
                 self.render(terminal)?;
 
                 let chord_match_start = SystemTime::now();
@@ -335,6 +848,11 @@ impl App {
                         if let AppSignal::Quit = signal {
                             break 'measure;
                         }
+                        if let AppSignal::VoiceLeading = signal {
+                            // takes effect from the next measure
+                            self.env.voice_leading = !self.env.voice_leading;
+                            self.render(terminal)?;
+                        }
                         if let AppSignal::Next = signal {
                             let next_end = SystemTime::now();
                             let next_duration = next_end.duration_since(chord_match_start)?;
@@ -359,16 +877,14 @@ impl App {
                     let any_key_vec = vec_rx.try_recv();
                     match any_key_vec {
                         Ok(key_vec) => {
-                            let key_vec: Vec<u8> = key_vec
-                                .into_iter()
-                                .map(|e| (e - 24 as u8) % 12 + 1)
-                                .collect();
-                            debug!("{:?}", key_vec); // debug!("{:?}", target_key_vec);
-                            if key_vec.len() >= 7 {
-                                debug!("kb check: {} keys pressed", key_vec.len());
+                            self.played = key_vec.into_iter().map(Pitch::from_midi).collect();
+                            debug!("{:?}", self.played); // debug!("{:?}", target_chord.voicing);
+                            if self.played.len() >= 7 {
+                                debug!("kb check: {} keys pressed", self.played.len());
                             }
+                            self.render(terminal)?;
 
-                            if key_vec == target_key_vec {
+                            if target_chord.matches(&self.played) {
                                 let chord_match_end = SystemTime::now();
                                 let chord_match_duration =
                                     chord_match_end.duration_since(chord_match_start)?;
@@ -379,7 +895,11 @@ impl App {
                                     let secs = (8 - chord_match_duration.as_secs());
                                     self.score += secs.pow(4) as i32;
                                 }
-                                self.phase = GamePhase::Matched { chord: target_chord.clone() };
+                                self.phase = GamePhase::Matched {
+                                    chord: target_chord.clone(),
+                                    numeral: numeral.clone(),
+                                    scale: scale.clone(),
+                                };
                                 self.render(terminal)?;
                                 self.phase = GamePhase::Score;
                                 self.render(terminal)?;
@@ -403,6 +923,122 @@ impl App {
         Ok(duration)
     }
 
+    /// Labels of the current chord followed by the next chords in playing
+    /// order: roman numerals, or on the tonnetz the transformation to the next.
+    pub(crate) fn labels(&self, playing: &[Chord]) -> Vec<String> {
+        match self.drill {
+            Drill::Functional
+            | Drill::Scale
+            | Drill::Cadence
+            | Drill::Template
+            | Drill::Interval => {
+                let mut numerals = analysis::roman_numerals(&self.next.key, playing);
+                numerals.insert(
+                    0,
+                    analysis::roman_numeral(&self.current.key, &self.current.chords[0]),
+                );
+                numerals
+            }
+            Drill::Tonnetz => {
+                let mut chords = Vec::from([self.current.chords[0].clone()]);
+                chords.extend_from_slice(playing);
+                neo_riemannian::labels(&chords)
+            }
+        }
+    }
+
+    /// Scales to improvise with over the current chord followed by the next
+    /// chords in playing order, each read in the key it is heard in.
+    pub(crate) fn chord_scales(&self, playing: &[Chord]) -> Result<Vec<Scale>, TheoryError> {
+        match self.drill {
+            Drill::Functional
+            | Drill::Scale
+            | Drill::Cadence
+            | Drill::Template
+            | Drill::Interval => {
+                let mut scales = Vec::from([analysis::chord_scale(
+                    &self.current.key,
+                    &self.current.chords[0],
+                )?]);
+                for chord in playing.iter() {
+                    scales.push(analysis::chord_scale(&self.next.key, chord)?);
+                }
+                Ok(scales)
+            }
+            Drill::Tonnetz => std::iter::once(&self.current.chords[0])
+                .chain(playing.iter())
+                .map(|e| analysis::chord_scale(&neo_riemannian::home_key(e), e))
+                .collect(),
+        }
+    }
+
+    /// Whether the next chords are to be played as they were built.
+    fn voicing_fixed(&self) -> bool {
+        matches!(self.drill, Drill::Cadence)
+            && self.cadence.as_ref().is_some_and(|e| e.voicing_fixed())
+    }
+
+    /// Voice the next chords in the voicing type of the session, keeping the
+    /// inversions they were sampled in.
+    fn voice_chords(&mut self) {
+        if self.voicing_fixed() {
+            return;
+        }
+        for chord in self.next.chords.iter_mut() {
+            match chord.restyled(self.voicing_type.clone()) {
+                Ok(restyled) => *chord = restyled,
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+
+    /// On fretted instruments, move every chord to the notes of its fingering,
+    /// so that the expected notes are ones the strings can sound. With voice
+    /// leading on, the next chords take the fingering nearest the one before
+    /// in playing order instead.
+    fn fret_chords(&mut self) {
+        let Some(tuning) = &self.env.tuning else {
+            return;
+        };
+        for chord in self.current.chords.iter_mut() {
+            if let Some(fretted) = fretboard::fret(chord, tuning) {
+                *chord = fretted;
+            }
+        }
+        // leading would move chords of a fixed voicing into other inversions
+        let leading = self.env.voice_leading && !self.voicing_fixed();
+        let mut previous = self.current.chords[0].clone();
+        for chord in self.next.chords.iter_mut().rev() {
+            let fretted = match leading {
+                true => fretboard::lead(&previous, chord, tuning),
+                false => fretboard::fret(chord, tuning),
+            };
+            if let Some(fretted) = fretted {
+                *chord = fretted;
+            }
+            previous = chord.clone();
+        }
+    }
+
+    /// Revoice the next chords in playing order, each one moving as little as
+    /// possible from the one before, starting from the current chord.
+    fn lead_voices(&mut self) {
+        if self.voicing_fixed() {
+            return;
+        }
+        let mut previous = self.current.chords[0].clone();
+        for chord in self.next.chords.iter_mut().rev() {
+            *chord = voice_leading::lead(
+                &previous,
+                chord,
+                &self.voicing_type,
+                self.difficulty.clone(),
+                self.env.voice_range,
+            );
+            previous = chord.clone();
+        }
+    }
+
     fn status_next_to_current(&mut self) {
         // TODO: use ref instead of clone?
         self.prevous_key = self.current.key.clone();
@@ -412,7 +1048,101 @@ impl App {
         self.next.key_iteration = 1;
     }
 
-    fn modulate(&mut self) -> anyhow::Result<()> {
+    /// Draw the next measure from the source of the drill.
+    fn advance(&mut self) -> Result<(), TheoryError> {
+        match self.drill {
+            Drill::Functional => {
+                let modulation = if self.current.key_iteration >= self.env.modulation_threshold {
+                    Modulation::sample(self.difficulty.clone())
+                } else {
+                    Ok(Modulation::SameKey)
+                };
+                modulation.and_then(|modulation| {
+                    self.modulation = modulation;
+                    self.modulate()
+                })
+            }
+            Drill::Tonnetz => self.walk(),
+            Drill::Cadence => self.cadence(),
+            Drill::Template => self.play_template(),
+            Drill::Scale => Scale::sample(self.difficulty.clone()).map(|scale| {
+                self.scale = Some(scale);
+            }),
+            Drill::Interval => Dyad::sample(self.difficulty.clone()).map(|dyad| {
+                self.dyad = Some(dyad);
+            }),
+        }
+    }
+
+    /// Move on along the tonnetz: the target becomes the current chord and
+    /// the next chords are random transformations away from it.
+    fn walk(&mut self) -> Result<(), TheoryError> {
+        self.pivot = None;
+        self.status_next_to_current();
+        self.next.chords = neo_riemannian::walk(
+            &self.current.chords[0],
+            self.env.walk,
+            self.difficulty.clone(),
+        )?;
+        self.next.key = neo_riemannian::home_key(&self.next.chords[0]);
+        Ok(())
+    }
+
+    /// Move on to another cadence, from the target of the last one into a
+    /// random key, in a mode that has the chords the cadence is named after.
+    fn cadence(&mut self) -> Result<(), TheoryError> {
+        self.pivot = None;
+        let cadence = Cadence::sample(self.difficulty.clone())?;
+        self.status_next_to_current();
+        self.next.key = cadence.sample_key(self.difficulty.clone())?;
+        self.next.chords = cadence.build_chords(&self.next.key, self.difficulty.clone())?;
+        self.cadence = Some(cadence);
+        Ok(())
+    }
+
+    /// Move on to the next phrase of the template, or once the chorus is over
+    /// to another template in another random key.
+    fn play_template(&mut self) -> Result<(), TheoryError> {
+        self.pivot = None;
+        self.status_next_to_current();
+        let (template, phrase) = match &self.template {
+            Some(template) if self.phrase < template.phrases().len() => {
+                (template.clone(), self.phrase)
+            }
+            _ => {
+                let template = Template::sample(self.difficulty.clone())?;
+                self.next.key = template.sample_key(self.difficulty.clone())?;
+                (template, 0)
+            }
+        };
+        self.next.chords =
+            template.build_chords(phrase, &self.next.key, self.difficulty.clone())?;
+        self.template = Some(template);
+        self.phrase = phrase + 1;
+        Ok(())
+    }
+
+    /// Shortest degree sequence through the changes as the key reads them,
+    /// with what it covers.
+    fn sequence(changes: &Changes, key: &Key) -> Result<(Vec<i8>, Coverage), TheoryError> {
+        let transitions = changes.transitions(key)?;
+        let ss = sequence::covering(&transitions);
+        if ss.is_empty() {
+            return Err(TheoryError::NoCandidate("change between degrees"));
+        }
+        let coverage = sequence::verify(&ss, &transitions);
+        debug!("App::sequence(): {} in {}: {}", changes, key, coverage);
+        Ok((ss, coverage))
+    }
+
+    /// Walk the changes in the key of the next chords, once the key has moved.
+    fn resequence(&mut self) -> Result<(), TheoryError> {
+        (self.ss, _) = Self::sequence(&self.changes, &self.next.key)?;
+        Ok(())
+    }
+
+    fn modulate(&mut self) -> Result<(), TheoryError> {
+        self.pivot = None;
         match self.modulation {
             Modulation::SameKey => {
                 // previous key not updated
@@ -423,34 +1153,32 @@ impl App {
                 self.next.key_iteration = self.current.key_iteration + 1;
 
                 self.current.ss_idx = self.next.ss_idx;
-                self.next.ss_idx = self.current.ss_idx + 1;
-                if self.next.ss_idx > (self.ss.len() - 1) {
-                    self.next.ss_idx %= (self.ss.len() - 1);
-                }
+                self.next.ss_idx = (self.current.ss_idx + 1) % self.ss.len();
 
                 self.next.key = self.current.key.clone();
-                let detour: DeTour = DeTour::sample(self.difficulty.clone())?;
+                let detour: DeTour = DeTour::sample(self.difficulty.clone(), &self.env.chain)?;
                 self.next.chords = detour.build_chords(
                     self.next
                         .key
-                        .gen_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
+                        .gen_extended_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
                     self.difficulty.clone(),
                 )?;
             }
             Modulation::ViaTonic => {
                 self.status_next_to_current();
                 self.current.ss_idx = self.next.ss_idx;
-                self.next.ss_idx = rand::thread_rng().gen_range(0..40);
 
                 self.next.key = Key::new(
                     self.current.key.tonic.clone(),
                     KeyType::sample(self.difficulty.clone())?,
                 );
-                let detour: DeTour = DeTour::sample(self.difficulty.clone())?;
+                self.resequence()?;
+                self.next.ss_idx = rand::thread_rng().gen_range(0..self.ss.len());
+                let detour: DeTour = DeTour::sample(self.difficulty.clone(), &self.env.chain)?;
                 self.next.chords = detour.build_chords(
                     self.next
                         .key
-                        .gen_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
+                        .gen_extended_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
                     self.difficulty.clone(),
                 )?;
             }
@@ -458,19 +1186,26 @@ impl App {
                 self.status_next_to_current();
 
                 self.current.ss_idx = self.next.ss_idx;
-                self.next.ss_idx = rand::thread_rng().gen_range(0..40);
 
-                let next_keys = self.current.chords[0].gen_major_keys();
-                let next_key_id = rand::thread_rng().gen_range(0..next_keys.len());
-                self.next.key = next_keys[next_key_id]
-                    .clone()
-                    .change_mode(rand::thread_rng().gen_range(0..7));
+                let next_key = self.current.chords[0]
+                    .gen_major_keys()
+                    .choose(&mut rand::thread_rng())
+                    .cloned()
+                    .ok_or(TheoryError::NoCandidate("key sharing the chord"))?;
+                self.next.key = next_key.change_mode(rand::thread_rng().gen_range(0..7))?;
+                self.resequence()?;
+                self.next.ss_idx = rand::thread_rng().gen_range(0..self.ss.len());
+                self.pivot = Some(Pivot {
+                    chord: self.current.chords[0].clone(),
+                    from: self.current.key.clone(),
+                    to: self.next.key.clone(),
+                });
 
-                let detour: DeTour = DeTour::sample(self.difficulty.clone())?;
+                let detour: DeTour = DeTour::sample(self.difficulty.clone(), &self.env.chain)?;
                 self.next.chords = detour.build_chords(
                     self.next
                         .key
-                        .gen_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
+                        .gen_extended_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
                     self.difficulty.clone(),
                 )?;
             }
@@ -483,18 +1218,20 @@ impl App {
                 let proxy_diminished = Chord::new(
                     self.current.chords[0].tonic.clone(),
                     ChordType::Diminished7,
-                    Inversion::sample(self.difficulty.clone())?,
-                );
-                let next_keys = proxy_diminished.gen_major_keys();
-                let next_key_id = rand::thread_rng().gen_range(0..next_keys.len());
-                self.next.key = next_keys[next_key_id]
-                    .clone()
-                    .change_mode(rand::thread_rng().gen_range(0..7));
+                    Voicing::sample(self.difficulty.clone())?,
+                )?;
+                let next_key = proxy_diminished
+                    .gen_major_keys()
+                    .choose(&mut rand::thread_rng())
+                    .cloned()
+                    .ok_or(TheoryError::NoCandidate("key around the diminished"))?;
+                self.next.key = next_key.change_mode(rand::thread_rng().gen_range(0..7))?;
 
                 let dominant_next_key = self.next.key.gen_chord(5, self.difficulty.clone())?;
                 let next_chord = self.next.key.gen_chord(1, self.difficulty.clone())?;
 
-                self.next.ss_idx = 1;
+                self.resequence()?;
+                self.next.ss_idx = 1 % self.ss.len();
                 self.next.chords = Vec::from([
                     next_chord,        // 1
                     dominant_next_key, // 5
@@ -505,15 +1242,84 @@ impl App {
                 let prev_key = self.prevous_key.clone();
                 self.status_next_to_current();
                 self.next.key = prev_key;
+                self.resequence()?;
 
                 self.current.ss_idx = self.next.ss_idx;
-                self.next.ss_idx = rand::thread_rng().gen_range(0..40);
+                self.next.ss_idx = rand::thread_rng().gen_range(0..self.ss.len());
 
-                let detour: DeTour = DeTour::sample(self.difficulty.clone())?;
+                let detour: DeTour = DeTour::sample(self.difficulty.clone(), &self.env.chain)?;
                 self.next.chords = detour.build_chords(
                     self.next
                         .key
-                        .gen_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
+                        .gen_extended_chord(self.ss[self.next.ss_idx], self.difficulty.clone())?,
+                    self.difficulty.clone(),
+                )?;
+            }
+            Modulation::ChromaticMediant => {
+                self.status_next_to_current();
+                self.current.ss_idx = self.next.ss_idx;
+
+                // home tonic straight into the mediant tonic, over the shared tone
+                self.next.key = self
+                    .current
+                    .key
+                    .gen_chromatic_mediants()
+                    .choose(&mut rand::thread_rng())
+                    .cloned()
+                    .ok_or(TheoryError::NoCandidate("chromatic mediant"))?;
+
+                let home_tonic = self.current.key.gen_chord(1, self.difficulty.clone())?;
+                let next_chord = self.next.key.gen_chord(1, self.difficulty.clone())?;
+
+                self.resequence()?;
+                self.next.ss_idx = 1 % self.ss.len();
+                self.next.chords = Vec::from([
+                    next_chord, // 1 of the mediant key
+                    home_tonic, // 1
+                ]);
+            }
+            Modulation::CommonTone => {
+                self.status_next_to_current();
+                self.current.ss_idx = self.next.ss_idx;
+
+                // a tone of the current chord is held into the new key's dominant
+                let next_keys: Vec<(Tone, Key)> = self.current.chords[0]
+                    .gen_common_tone_keys()
+                    .into_iter()
+                    .filter(|(_, key)| key.tonic.idx != self.current.key.tonic.idx)
+                    .collect();
+                let (common_tone, next_key) = next_keys
+                    .choose(&mut rand::thread_rng())
+                    .cloned()
+                    .ok_or(TheoryError::NoCandidate("common tone key"))?;
+                debug!("App::modulate(): {} held into {}", common_tone, next_key);
+                self.next.key = next_key;
+
+                let dominant_next_key = self.next.key.gen_chord(5, self.difficulty.clone())?;
+                let next_chord = self.next.key.gen_chord(1, self.difficulty.clone())?;
+
+                self.resequence()?;
+                self.next.ss_idx = 1 % self.ss.len();
+                self.next.chords = Vec::from([
+                    next_chord,        // 1
+                    dominant_next_key, // 5, holding the common tone
+                ]);
+            }
+            Modulation::Borrowed => {
+                // as SameKey, but the target is borrowed from a parallel mode
+                self.current.key = self.next.key.clone();
+                self.current.chords = Vec::from([self.next.chords[0].clone()]);
+
+                self.current.key_iteration = self.next.key_iteration;
+                self.next.key_iteration = self.current.key_iteration + 1;
+
+                self.current.ss_idx = self.next.ss_idx;
+                self.next.ss_idx = (self.current.ss_idx + 1) % self.ss.len();
+
+                self.next.key = self.current.key.clone();
+                let detour: DeTour = DeTour::sample(self.difficulty.clone(), &self.env.chain)?;
+                self.next.chords = detour.build_chords(
+                    self.next.key.gen_borrowed_chord(self.difficulty.clone())?,
                     self.difficulty.clone(),
                 )?;
             }
@@ -525,15 +1331,36 @@ impl App {
 impl Iterator for App {
     type Item = Chord;
 
+    /// Step to the next measure. A failed step is rolled back and drawn again
+    /// from the same drill, a functional measure then staying in the key;
+    /// when that fails too the last measure is played again.
     fn next(&mut self) -> Option<Chord> {
-        let modulation: Modulation;
-        if self.current.key_iteration >= self.env.modulation_threshold {
-            modulation = Modulation::sample(self.difficulty.clone()).unwrap();
-        } else {
-            modulation = Modulation::SameKey;
+        self.error = None;
+        let saved = (
+            self.prevous_key.clone(),
+            self.current.clone(),
+            self.next.clone(),
+            self.ss.clone(),
+        );
+        let result = self.advance();
+        if let Err(e) = result {
+            error!("App::next(): {} failed: {}", self.drill, e);
+            (self.prevous_key, self.current, self.next, self.ss) = saved.clone();
+            self.error = Some(e);
+            // a functional measure stays in the key, other drills draw again
+            let retry = match self.drill {
+                Drill::Functional => {
+                    self.modulation = Modulation::SameKey;
+                    self.modulate()
+                }
+                _ => self.advance(),
+            };
+            if let Err(e) = retry {
+                error!("App::next(): retrying {} failed: {}", self.drill, e);
+                (self.prevous_key, self.current, self.next, self.ss) = saved;
+                self.error = Some(e);
+            }
         }
-        self.modulation = modulation;
-        self.modulate();
         Some(self.current.chords[0].clone())
     }
 }
@@ -36,6 +36,57 @@ pub(super) fn new_input_thread() -> anyhow::Result<Receiver<AppSignal>> {
                     if let KeyCode::Char('g') = key.code {
                         input_tx.send(AppSignal::Guitar);
                     }
+                    if let KeyCode::Char('f') = key.code {
+                        input_tx.send(AppSignal::Functional);
+                    }
+                    if let KeyCode::Char('t') = key.code {
+                        input_tx.send(AppSignal::Tonnetz);
+                    }
+                    if let KeyCode::Char('s') = key.code {
+                        input_tx.send(AppSignal::Scale);
+                    }
+                    if let KeyCode::Char('d') = key.code {
+                        input_tx.send(AppSignal::Cadence);
+                    }
+                    if let KeyCode::Char('l') = key.code {
+                        input_tx.send(AppSignal::Template);
+                    }
+                    if let KeyCode::Char('i') = key.code {
+                        input_tx.send(AppSignal::Interval);
+                    }
+                    if let KeyCode::Char('c') = key.code {
+                        input_tx.send(AppSignal::Close);
+                    }
+                    if let KeyCode::Char('2') = key.code {
+                        input_tx.send(AppSignal::Drop2);
+                    }
+                    if let KeyCode::Char('3') = key.code {
+                        input_tx.send(AppSignal::Drop3);
+                    }
+                    if let KeyCode::Char('4') = key.code {
+                        input_tx.send(AppSignal::Drop24);
+                    }
+                    if let KeyCode::Char('e') = key.code {
+                        input_tx.send(AppSignal::Spread);
+                    }
+                    if let KeyCode::Char('a') = key.code {
+                        input_tx.send(AppSignal::RootlessA);
+                    }
+                    if let KeyCode::Char('b') = key.code {
+                        input_tx.send(AppSignal::RootlessB);
+                    }
+                    if let KeyCode::Char('u') = key.code {
+                        input_tx.send(AppSignal::UpperStructure);
+                    }
+                    if let KeyCode::Char('n') = key.code {
+                        input_tx.send(AppSignal::Tuning);
+                    }
+                    if let KeyCode::Char('v') = key.code {
+                        input_tx.send(AppSignal::VoiceLeading);
+                    }
+                    if let KeyCode::Char('h') = key.code {
+                        input_tx.send(AppSignal::Changes);
+                    }
                     if let KeyCode::Enter = key.code {
                         input_tx.send(AppSignal::Next);
                     }
@@ -51,7 +102,7 @@ pub(super) fn new_input_thread() -> anyhow::Result<Receiver<AppSignal>> {
     Ok(input_rx)
 }
 
-pub(super) struct MIDI {
+pub(super) struct Midi {
     device_name: String,
     pub(super) input: MidiInput,
     pub(super) input_port: MidiInputPort,
@@ -59,13 +110,13 @@ pub(super) struct MIDI {
     pub(super) output_port: MidiOutputPort,
 }
 
-impl fmt::Debug for MIDI {
+impl fmt::Debug for Midi {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.device_name)
     }
 }
 
-impl MIDI {
+impl Midi {
     pub(super) fn new() -> Result<Self, Box<dyn Error>> {
         let input = MidiInput::new("input")?;
         let output = MidiOutput::new("output")?;
@@ -77,7 +128,7 @@ impl MIDI {
         let output_port = Self::select_port(&output)?;
         let device_name = input.port_name(&input_port)?;
 
-        Ok(MIDI {
+        Ok(Midi {
             device_name,
             input,
             input_port,
@@ -110,5 +161,22 @@ pub enum AppSignal {
     Quit,
     Piano,
     Guitar,
+    Tuning,
+    Functional,
+    Tonnetz,
+    Scale,
+    Cadence,
+    Template,
+    Interval,
+    Close,
+    Drop2,
+    Drop3,
+    Drop24,
+    Spread,
+    RootlessA,
+    RootlessB,
+    UpperStructure,
     Next,
+    VoiceLeading,
+    Changes,
 }
@@ -6,7 +6,7 @@ use std::{error::Error, sync::mpsc};
 
 use crate::{
     app::App,
-    input::{new_input_thread, MIDI},
+    input::{new_input_thread, Midi},
 };
 
 mod app;
@@ -1,6 +1,10 @@
 use crate::{
     app::{App, Difficulty},
-    theory::chord::Chord,
+    theory::{
+        chord::{Chord, VoicingType},
+        fretboard::Tuning,
+        sequence::{Changes, Coverage},
+    },
 };
 use colored::*;
 use std::fmt;
@@ -100,6 +104,250 @@ pub(super) fn guitar_selected() {
     );
 }
 
+pub(super) fn select_tuning(tuning: &Tuning) {
+    println!(
+        "
+                {}
+                {}
+                {}
+
+                {} {}
+        ",
+        "-------------".cyan().bold(),
+        "select tuning".cyan().bold(),
+        "-------------".cyan().bold(),
+        tuning,
+        "([n]ext / enter to keep)".cyan().bold(),
+    );
+}
+
+pub(super) fn tuning_selected(tuning: &Tuning) {
+    println!(
+        "
+                {}
+                {} {}
+                {}
+        ",
+        "----------------".purple().bold(),
+        tuning,
+        "selected".purple().bold(),
+        "----------------".purple().bold(),
+    );
+}
+
+pub(super) fn select_drill() {
+    println!(
+        "
+                {}
+                {}
+                {}
+
+                {} / {} / {} / {} / {} / {}
+        ",
+        "------------".cyan().bold(),
+        "select drill".cyan().bold(),
+        "------------".cyan().bold(),
+        "[f]unctional".cyan().bold(),
+        "[t]onnetz".red().bold(),
+        "[s]cale".yellow().bold(),
+        "ca[d]ence".green().bold(),
+        "temp[l]ate".purple().bold(),
+        "[i]nterval".blue().bold(),
+    );
+}
+
+pub(super) fn functional_selected() {
+    println!(
+        "
+                {}
+                {}
+                {}
+        ",
+        "---------------------".cyan().bold(),
+        "[f]unctional selected".cyan().bold(),
+        "---------------------".cyan().bold(),
+    );
+}
+
+pub(super) fn tonnetz_selected() {
+    println!(
+        "
+                {}
+                {}
+                {}
+        ",
+        "------------------".red().bold(),
+        "[t]onnetz selected".red().bold(),
+        "------------------".red().bold(),
+    );
+}
+
+pub(super) fn scale_selected() {
+    println!(
+        "
+                {}
+                {}
+                {}
+        ",
+        "----------------".yellow().bold(),
+        "[s]cale selected".yellow().bold(),
+        "----------------".yellow().bold(),
+    );
+}
+
+pub(super) fn cadence_selected() {
+    println!(
+        "
+                {}
+                {}
+                {}
+        ",
+        "------------------".green().bold(),
+        "ca[d]ence selected".green().bold(),
+        "------------------".green().bold(),
+    );
+}
+
+pub(super) fn template_selected() {
+    println!(
+        "
+                {}
+                {}
+                {}
+        ",
+        "-------------------".purple().bold(),
+        "temp[l]ate selected".purple().bold(),
+        "-------------------".purple().bold(),
+    );
+}
+
+pub(super) fn interval_selected() {
+    println!(
+        "
+                {}
+                {}
+                {}
+        ",
+        "-------------------".blue().bold(),
+        "[i]nterval selected".blue().bold(),
+        "-------------------".blue().bold(),
+    );
+}
+
+pub(super) fn select_voicing() {
+    println!(
+        "
+                {}
+                {}
+                {}
+
+                {} / {} / {} / {} / {}
+                {} / {} / {}
+        ",
+        "--------------".cyan().bold(),
+        "select voicing".cyan().bold(),
+        "--------------".cyan().bold(),
+        "[c]lose".white().bold(),
+        "drop-[2]".green().bold(),
+        "drop-[3]".blue().bold(),
+        "drop-2-and-[4]".cyan().bold(),
+        "spr[e]ad".purple().bold(),
+        "rootless [a]".yellow().bold(),
+        "rootless [b]".yellow().bold(),
+        "[u]pper structure".red().bold(),
+    );
+}
+
+pub(super) fn voicing_selected(voicing_type: &VoicingType) {
+    println!(
+        "
+                {}
+                {} {}
+                {}
+        ",
+        "----------------".cyan().bold(),
+        voicing_type,
+        "selected".cyan().bold(),
+        "----------------".cyan().bold(),
+    );
+}
+
+pub(super) fn select_voice_leading(voice_leading: bool) {
+    let state = match voice_leading {
+        true => "on".green().bold(),
+        false => "off".red().bold(),
+    };
+    println!(
+        "
+                {}
+                {}
+                {}
+
+                {} {} {}
+        ",
+        "-------------".cyan().bold(),
+        "voice leading".cyan().bold(),
+        "-------------".cyan().bold(),
+        "voice leading".white().bold(),
+        state,
+        "([v] to switch / enter to keep)".cyan().bold(),
+    );
+}
+
+pub(super) fn voice_leading_selected(voice_leading: bool) {
+    let state = match voice_leading {
+        true => "voice leading on",
+        false => "voice leading off",
+    };
+    println!(
+        "
+                {}
+                {}
+                {}
+        ",
+        "-----------------".cyan().bold(),
+        state.cyan().bold(),
+        "-----------------".cyan().bold(),
+    );
+}
+
+pub(super) fn select_changes(changes: &Changes) {
+    println!(
+        "
+                {}
+                {}
+                {}
+
+                {} {}
+        ",
+        "--------------".cyan().bold(),
+        "select changes".cyan().bold(),
+        "--------------".cyan().bold(),
+        changes,
+        "([h] to switch / enter to keep)".cyan().bold(),
+    );
+}
+
+pub(super) fn changes_selected(changes: &Changes, coverage: &Coverage) {
+    let coverage = match coverage.is_complete() {
+        true => format!("{}", coverage).green().bold(),
+        false => format!("{}", coverage).red().bold(),
+    };
+    println!(
+        "
+                {}
+                {} {}
+                {}
+                {}
+        ",
+        "----------------".purple().bold(),
+        changes,
+        "selected".purple().bold(),
+        coverage,
+        "----------------".purple().bold(),
+    );
+}
+
 pub(super) fn get_ready() {
     println!(
         "
@@ -243,85 +491,51 @@ pub(super) fn summary(duration: u64, difficulty: &Difficulty, score: i32) {
 
 impl fmt::Display for App {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.next.chords.len() == 3 {
-            write!(
-                f,
-                "
+        if self.current.chords.is_empty() || self.next.chords.is_empty() {
+            return write!(f, "{}", "no chords in this measure".red().bold());
+        }
+        write!(
+            f,
+            "
 
                 {}
 
                 {}: {}
                 {}
-
-                => {}:
-                  -> {}
-                   --> {}
-                    ---> {}
-
-                {}
-
-                ",
-                "----------- MEASURE START -----------".cyan().bold(),
-                self.current.key,
-                self.current.chords[0],
-                self.modulation,
-                self.next.key,
-                self.next.chords[2],
-                self.next.chords[1],
-                self.next.chords[0],
-                "------------ MEASURE END ------------".cyan().bold(),
-            )
-        } else if self.next.chords.len() == 2 {
+",
+            "----------- MEASURE START -----------".cyan().bold(),
+            self.current.key,
+            self.current.chords[0],
+            self.modulation,
+        )?;
+        if self.next.chords.len() == 1 {
             write!(
                 f,
                 "
-
-                {}
-
-                {}: {}
-                {}
-
-                => {}:
-                  -> {}
-                    --> {}
-
-                {}
-
-                ",
-                "----------- MEASURE START -----------".cyan().bold(),
-                self.current.key,
-                self.current.chords[0],
-                self.modulation,
-                self.next.key,
-                self.next.chords[1],
-                self.next.chords[0],
-                "------------ MEASURE END ------------".cyan().bold(),
-            )
-        } else if self.next.chords.len() == 1 {
+                => {}: {}
+",
+                self.next.key, self.next.chords[0],
+            )?;
+        } else {
             write!(
                 f,
                 "
-
-                {}
-
-                {}: {}
-                {}
-
-                => {}: {}
-
+                => {}:
+",
+                self.next.key
+            )?;
+            // chords are stored target first, print them in playing order
+            for (i, chord) in self.next.chords.iter().rev().enumerate() {
+                writeln!(f, "{}{}> {}", " ".repeat(18 + i), "-".repeat(i + 1), chord)?;
+            }
+        }
+        write!(
+            f,
+            "
                 {}
 
                 ",
-                "----------- MEASURE START -----------".cyan().bold(),
-                self.current.key,
-                self.current.chords[0],
-                self.modulation,
-                self.next.key,
-                self.next.chords[0],
-                "------------ MEASURE END ------------".cyan().bold(),
-            )
-        } else {
-            panic!("invalid chord sequence")
-        }
+            "------------ MEASURE END ------------".cyan().bold(),
+        )
     }
 }
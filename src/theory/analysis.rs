@@ -0,0 +1,282 @@
+use log::debug;
+use std::fmt;
+
+use super::{
+    chord::{Chord, ChordType},
+    error::TheoryError,
+    key::{Key, KeyType},
+    scale::{Scale, ScaleType},
+};
+
+pub(crate) const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+/// Pitch class (1-12) a number of semitones away from `idx`.
+fn shift(idx: i8, semitones: i8) -> i8 {
+    (idx - 1 + semitones).rem_euclid(12) + 1
+}
+
+/// Degree (1-7) of the key whose tone has the given pitch class.
+fn degree(key: &Key, idx: i8) -> Option<i8> {
+    (1..=7).find(|&i| key.degree_idx(i) == idx)
+}
+
+fn numeral(degree: i8, minor: bool) -> String {
+    let numeral = NUMERALS[(degree - 1) as usize];
+    if minor {
+        numeral.to_lowercase()
+    } else {
+        numeral.to_string()
+    }
+}
+
+fn suffix(chord_type: &ChordType) -> &'static str {
+    match chord_type {
+        ChordType::Major => "",
+        ChordType::Minor => "",
+        ChordType::Major7 => "maj7",
+        ChordType::Minor7 => "7",
+        ChordType::Dominant7 => "7",
+        ChordType::HalfDiminished7 => "\u{00F8}7",
+        ChordType::Diminished7 => "\u{00B0}7",
+        ChordType::Major6 => "6",
+        ChordType::Minor6 => "6",
+        ChordType::Major9 => "maj9",
+        ChordType::Minor9 => "9",
+        ChordType::Dominant9 => "9",
+        ChordType::Dominant11 => "11",
+        ChordType::Dominant13 => "13",
+        ChordType::Dominant7Sus4 => "7sus4",
+        ChordType::Dominant7Flat9 => "7b9",
+        ChordType::Dominant7Sharp9 => "7#9",
+        ChordType::Dominant7Sharp11 => "7#11",
+        ChordType::Dominant7Flat13 => "7b13",
+        ChordType::Altered => "7alt",
+        ChordType::MinorMajor7 => "maj7",
+        ChordType::Augmented => "+",
+        ChordType::Augmented7 => "+7",
+        ChordType::AugmentedMajor7 => "+maj7",
+    }
+}
+
+/// Label of a chord that tonicizes a degree, e.g. "V7/vi"; the "/I" is dropped.
+fn applied(function: &str, chord_type: &ChordType, key: &Key, target: i8) -> String {
+    match target {
+        1 => format!("{}{}", function, suffix(chord_type)),
+        _ => format!(
+            "{}{}/{}",
+            function,
+            suffix(chord_type),
+            numeral(
+                target,
+                key.gen_chord_type(target).is_ok_and(|t| t.is_minor())
+            )
+        ),
+    }
+}
+
+/// Roman numeral of a chord in the key, e.g. "ii7", "V7/vi", "subV7/IV",
+/// "vii°7/V" or "bVII7" for chords borrowed from outside the key.
+pub(crate) fn roman_numeral(key: &Key, chord: &Chord) -> String {
+    let root = chord.tonic.idx;
+    let chord_type = &chord.chord_type;
+    let scale: Vec<i8> = (1..=7).map(|i| key.degree_idx(i)).collect();
+    let diatonic = chord.tones.iter().all(|e| scale.contains(&e.idx));
+
+    if diatonic {
+        if let Some(d) = degree(key, root) {
+            return format!(
+                "{}{}",
+                numeral(d, chord_type.is_minor()),
+                suffix(chord_type)
+            );
+        }
+    }
+    if chord_type.is_dominant() {
+        // a diminished degree is no target to tonicize, e.g. Gb7 in C is subV7/IV
+        let tonicizable = |target: i8| {
+            !matches!(
+                key.gen_chord_type(target),
+                Ok(ChordType::Diminished7 | ChordType::HalfDiminished7)
+            )
+        };
+        if let Some(target) = degree(key, shift(root, 5)).filter(|&e| tonicizable(e)) {
+            return applied("V", chord_type, key, target);
+        }
+        // a dominant on bVII is read as the backdoor dominant, not as subV/vi
+        let backdoor = shift(root, 2) == key.degree_idx(1);
+        if let (Some(target), false) = (degree(key, shift(root, -1)), backdoor) {
+            return applied("subV", chord_type, key, target);
+        }
+    }
+    if let ChordType::Diminished7 | ChordType::HalfDiminished7 = chord_type {
+        if let Some(target) = degree(key, shift(root, 1)) {
+            return applied("vii", chord_type, key, target);
+        }
+    }
+
+    let (accidental, d) = match degree(key, root) {
+        Some(d) => ("", d),
+        None => match degree(key, shift(root, 1)) {
+            Some(d) => ("b", d),
+            None => ("#", degree(key, shift(root, -1)).unwrap_or(1)),
+        },
+    };
+    format!(
+        "{}{}{}",
+        accidental,
+        numeral(d, chord_type.is_minor()),
+        suffix(chord_type)
+    )
+}
+
+/// Roman numerals of chords in playing order. A minor chord leading into an
+/// applied dominant a fourth above is labelled as its related ii, e.g. "ii7/V".
+pub(crate) fn roman_numerals(key: &Key, chords: &[Chord]) -> Vec<String> {
+    let mut numerals: Vec<String> = chords.iter().map(|e| roman_numeral(key, e)).collect();
+    for i in 1..chords.len() {
+        let (pre, chord) = (&chords[i - 1], &chords[i]);
+        let related_ii = pre.chord_type.is_minor()
+            && pre.chord_type != ChordType::Diminished7
+            && chord.chord_type.is_dominant()
+            && chord.tonic.idx == shift(pre.tonic.idx, 5);
+        if !related_ii || !numerals[i].starts_with('V') {
+            continue;
+        }
+        if let Some((_, target)) = numerals[i].clone().split_once('/') {
+            numerals[i - 1] = format!("ii{}/{}", suffix(&pre.chord_type), target);
+        }
+    }
+    debug!(
+        "roman_numerals(): {} in {}: {:?}",
+        chords.len(),
+        key,
+        numerals
+    );
+    numerals
+}
+
+/// Scale to improvise with over a chord, from its function in the key: the
+/// mode of the key on a diatonic degree, Locrian natural 2 over m7b5 and
+/// half-whole over dim7; other chords take the usual scale of their quality.
+pub(crate) fn chord_scale(key: &Key, chord: &Chord) -> Result<Scale, TheoryError> {
+    let scale: Vec<i8> = (1..=7).map(|i| key.degree_idx(i)).collect();
+    let diatonic = chord.tones.iter().all(|e| scale.contains(&e.idx));
+    let mode = degree(key, chord.tonic.idx).and_then(|d| key.change_mode(d - 1).ok());
+    let diminished = matches!(
+        chord.chord_type,
+        ChordType::Diminished7 | ChordType::HalfDiminished7
+    );
+    match mode {
+        Some(mode) if diatonic && !diminished => mode.scale(),
+        _ => Scale::new(chord.tonic.clone(), quality_scale(key, chord)),
+    }
+}
+
+/// Scale of a chord by its quality; plain dominants also look at where they lead.
+fn quality_scale(key: &Key, chord: &Chord) -> ScaleType {
+    let root = chord.tonic.idx;
+    match &chord.chord_type {
+        ChordType::Diminished7 => ScaleType::HalfWhole,
+        ChordType::HalfDiminished7 => ScaleType::Mode(KeyType::LocrianNatural2),
+        ChordType::Major | ChordType::Major6 => ScaleType::Mode(KeyType::Ionian),
+        // a major seventh from outside the key, e.g. bVImaj7, avoids the 4th
+        ChordType::Major7 | ChordType::Major9 => ScaleType::Mode(KeyType::Lydian),
+        ChordType::Minor | ChordType::Minor7 | ChordType::Minor6 | ChordType::Minor9 => {
+            ScaleType::Mode(KeyType::Dorian)
+        }
+        ChordType::MinorMajor7 => ScaleType::Mode(KeyType::MelodicMinor),
+        ChordType::Augmented | ChordType::Augmented7 => ScaleType::WholeTone,
+        ChordType::AugmentedMajor7 => ScaleType::Mode(KeyType::LydianAugmented),
+        ChordType::Dominant7Sus4 => ScaleType::Mode(KeyType::Mixolydian),
+        ChordType::Dominant7Flat9 => ScaleType::HalfWhole,
+        ChordType::Dominant7Sharp9 | ChordType::Altered => ScaleType::Mode(KeyType::Altered),
+        ChordType::Dominant7Flat13 => ScaleType::Mode(KeyType::MixolydianFlat6),
+        ChordType::Dominant7Sharp11 => ScaleType::Mode(KeyType::LydianDominant),
+        ChordType::Dominant7
+        | ChordType::Dominant9
+        | ChordType::Dominant11
+        | ChordType::Dominant13 => {
+            // an applied dominant is altered into a minor chord and mixolydian
+            // into a major one; a tritone sub or backdoor dominant is lydian
+            let into_minor = |target: i8| key.gen_chord_type(target).is_ok_and(|t| t.is_minor());
+            match degree(key, shift(root, 5)) {
+                Some(target) if into_minor(target) => ScaleType::Mode(KeyType::Altered),
+                Some(_) => ScaleType::Mode(KeyType::Mixolydian),
+                None if degree(key, shift(root, -1)).is_some() => {
+                    ScaleType::Mode(KeyType::LydianDominant)
+                }
+                None => ScaleType::Mode(KeyType::Mixolydian),
+            }
+        }
+    }
+}
+
+/// A chord shared by two keys, explaining a modulation through it,
+/// e.g. "Dm7 = ii7 in C = vi7 in F".
+#[derive(Debug, Clone)]
+pub(crate) struct Pivot {
+    pub(crate) chord: Chord,
+    pub(crate) from: Key,
+    pub(crate) to: Key,
+}
+
+impl fmt::Display for Pivot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{} = {} in {} = {} in {}",
+            self.chord.tonic,
+            self.chord.chord_type,
+            roman_numeral(&self.from, &self.chord),
+            self.from,
+            roman_numeral(&self.to, &self.chord),
+            self.to
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numeral_in(key: &str, chord: &str) -> String {
+        roman_numeral(&key.parse().unwrap(), &chord.parse().unwrap())
+    }
+
+    #[test]
+    fn diatonic_chords_take_their_degree() {
+        assert_eq!(numeral_in("C", "Dm7"), "ii7");
+        assert_eq!(numeral_in("C", "G7"), "V7");
+        assert_eq!(numeral_in("C", "Bm7b5"), "vii\u{00F8}7");
+        assert_eq!(numeral_in("A aeolian", "Fmaj7"), "VImaj7");
+    }
+
+    #[test]
+    fn applied_dominants_name_their_target() {
+        assert_eq!(numeral_in("C", "E7"), "V7/vi");
+        assert_eq!(numeral_in("C", "D7"), "V7/V");
+        assert_eq!(numeral_in("C", "C7"), "V7/IV");
+    }
+
+    #[test]
+    fn tritone_subs_skip_diminished_targets() {
+        assert_eq!(numeral_in("C", "Gb7"), "subV7/IV");
+        assert_eq!(numeral_in("C", "Db7"), "subV7");
+        assert_eq!(numeral_in("C", "Bb7"), "bVII7");
+    }
+
+    #[test]
+    fn leading_tone_chords_name_their_target() {
+        assert_eq!(numeral_in("C", "F#dim7"), "vii\u{00B0}7/V");
+    }
+
+    #[test]
+    fn minor_chords_before_applied_dominants_are_related_iis() {
+        let key: Key = "C".parse().unwrap();
+        let chords: Vec<Chord> = ["Em7", "A7", "Dm7"]
+            .iter()
+            .map(|e| e.parse().unwrap())
+            .collect();
+        assert_eq!(roman_numerals(&key, &chords), ["ii7/ii", "V7/ii", "ii7"]);
+    }
+}
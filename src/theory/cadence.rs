@@ -0,0 +1,167 @@
+use colored::*;
+use log::{debug, info};
+use rand::prelude::*;
+use statrs::distribution::Categorical;
+use std::fmt;
+
+use crate::app::Difficulty;
+use super::{
+    chord::{Chord, ChordType, Voicing, VoicingType},
+    error::TheoryError,
+    key::Key,
+};
+
+/// Keys drawn before giving up on a mode that has the chords of a cadence.
+const MAX_DRAWS: usize = 200;
+
+/// Cadences closing a phrase, each named after the chords it ends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Cadence {
+    Authentic, // ii or IV - V - I
+    Plagal,    // IV - I, iv - i in minor
+    Half,      // I, ii or IV - V, left open on the dominant
+    Deceptive, // ii or IV - V - vi, the tonic swapped for the submediant
+    Phrygian,  // iv6 - V in minor, the bass falling a half step onto 5
+}
+
+impl fmt::Display for Cadence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cadence::Authentic => {
+                write!(f, "{}", "Authentic".green().bold())
+            }
+            Cadence::Plagal => {
+                write!(f, "{}", "Plagal".blue().bold())
+            }
+            Cadence::Half => {
+                write!(f, "{}", "Half".yellow().bold())
+            }
+            Cadence::Deceptive => {
+                write!(f, "{}", "Deceptive".red().bold())
+            }
+            Cadence::Phrygian => {
+                write!(f, "{}", "Phrygian".purple().bold())
+            }
+        }
+    }
+}
+
+impl Cadence {
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
+        let mut rng_seed = rand::thread_rng();
+        // the random walk hardly ever lands on the last two, so they come up as often
+        let prob = match difficulty {
+            Difficulty::Piano => [1.0; 5],
+            Difficulty::Guitar => [1.0; 5],
+        };
+
+        let mnm = Categorical::new(&prob)?;
+        let cadence = match mnm.sample(&mut rng_seed) as i32 {
+            0 => Cadence::Authentic,
+            1 => Cadence::Plagal,
+            2 => Cadence::Half,
+            3 => Cadence::Deceptive,
+            4 => Cadence::Phrygian,
+            idx => {
+                return Err(TheoryError::Sample {
+                    what: "cadence",
+                    idx,
+                })
+            }
+        };
+        info!("Cadence::sample(): {} sampled", cadence);
+        Ok(cadence)
+    }
+
+    /// Whether the diatonic chords of the mode are the ones the cadence is
+    /// named after, e.g. a dominant seventh a perfect fifth up to resolve
+    /// authentically.
+    fn fits(&self, key: &Key) -> bool {
+        let is = |idx: i8, chord_types: &[ChordType]| {
+            key.gen_chord_type(idx)
+                .map(|e| chord_types.contains(&e))
+                .unwrap_or(false)
+        };
+        let above = |idx: i8| (key.degree_idx(idx) - key.degree_idx(1)).rem_euclid(12);
+        let dominant = above(5) == 7 && is(5, &[ChordType::Dominant7]);
+        match self {
+            Cadence::Authentic | Cadence::Half => dominant,
+            Cadence::Plagal => {
+                above(4) == 5
+                    && is(
+                        4,
+                        &[ChordType::Major7, ChordType::Minor7, ChordType::Dominant7],
+                    )
+                    && is(
+                        1,
+                        &[
+                            ChordType::Major7,
+                            ChordType::Minor7,
+                            ChordType::Dominant7,
+                            ChordType::MinorMajor7,
+                        ],
+                    )
+            }
+            Cadence::Deceptive => dominant && is(6, &[ChordType::Major7, ChordType::Minor7]),
+            Cadence::Phrygian => dominant && is(4, &[ChordType::Minor7, ChordType::MinorMajor7]),
+        }
+    }
+
+    /// A random key whose mode has the chords of the cadence. Keys are drawn
+    /// until one fits, so that they stay spelled as the sampled keys are.
+    pub(crate) fn sample_key(&self, difficulty: Difficulty) -> Result<Key, TheoryError> {
+        for _ in 0..MAX_DRAWS {
+            let key = Key::sample(difficulty.clone())?;
+            if self.fits(&key) {
+                debug!("Cadence::sample_key(): {} in {}", self, key);
+                return Ok(key);
+            }
+        }
+        Err(TheoryError::NoCandidate("mode for the cadence"))
+    }
+
+    /// Whether the chords keep the voicing they are built in, as the Phrygian
+    /// cadence does for its bass line, which restyling would undo.
+    pub(crate) fn voicing_fixed(&self) -> bool {
+        matches!(self, Cadence::Phrygian)
+    }
+
+    /// Chords of the cadence in the key, stored target first as the detours
+    /// are. The predominant is ii or IV at random, and the half cadence may
+    /// also come from I.
+    pub(crate) fn build_chords(
+        &self,
+        key: &Key,
+        difficulty: Difficulty,
+    ) -> Result<Vec<Chord>, TheoryError> {
+        let pre = if rand::thread_rng().gen_bool(0.5) {
+            2
+        } else {
+            4
+        };
+        let degrees: Vec<i8> = match self {
+            Cadence::Authentic => vec![1, 5, pre],
+            Cadence::Plagal => vec![1, 4],
+            // I, ii and IV a third of the time each
+            Cadence::Half => match rand::thread_rng().gen_range(0..3) {
+                0 => vec![5, 1],
+                _ => vec![5, pre],
+            },
+            Cadence::Deceptive => vec![6, 5, pre],
+            Cadence::Phrygian => vec![5, 4],
+        };
+        let mut chords = Vec::new();
+        for idx in degrees.into_iter() {
+            chords.push(key.gen_chord(idx, difficulty.clone())?);
+        }
+        if let Cadence::Phrygian = self {
+            // iv in first inversion and V in root position, in close position
+            // so that b6 stays in the bass and falls onto 5
+            for (chord, inversion) in chords.iter_mut().zip([0, 1]) {
+                let layout = Voicing::new(VoicingType::Close, inversion, &difficulty);
+                *chord = Chord::new(chord.tonic.clone(), chord.chord_type.clone(), layout)?;
+            }
+        }
+        Ok(chords)
+    }
+}
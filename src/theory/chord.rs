@@ -1,5 +1,5 @@
 use colored::*;
-use log::info;
+use log::{debug, info};
 use rand::{
     distributions::{Distribution, Standard},
     prelude::*,
@@ -11,22 +11,49 @@ use std::{error::Error, fmt};
 
 use crate::app::Difficulty;
 use super::{
+    error::TheoryError,
     key::{Key, KeyType},
+    pitch::{self, Pitch},
     tone::{Interval, Tone},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum ChordType {
+    Major,
+    Minor,
     Major7,
     Minor7,
     Dominant7,
     HalfDiminished7,
     Diminished7,
+    Major6,
+    Minor6,
+    Major9,
+    Minor9,
+    Dominant9,
+    Dominant11,
+    Dominant13,
+    Dominant7Sus4,
+    Dominant7Flat9,
+    Dominant7Sharp9,
+    Dominant7Sharp11,
+    Dominant7Flat13,
+    Altered,
+    MinorMajor7,
+    Augmented,
+    Augmented7,
+    AugmentedMajor7,
 }
 
 impl fmt::Display for ChordType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            ChordType::Major => {
+                write!(f, "{}", "".green().bold())
+            }
+            ChordType::Minor => {
+                write!(f, "{}", "m".blue().bold())
+            }
             ChordType::Major7 => {
                 write!(f, "{}", "M7".green().bold())
             }
@@ -42,92 +69,404 @@ impl fmt::Display for ChordType {
             ChordType::Diminished7 => {
                 write!(f, "{}", "dim7".red().bold())
             }
+            ChordType::Major6 => {
+                write!(f, "{}", "6".green().bold())
+            }
+            ChordType::Minor6 => {
+                write!(f, "{}", "m6".blue().bold())
+            }
+            ChordType::Major9 => {
+                write!(f, "{}", "M9".green().bold())
+            }
+            ChordType::Minor9 => {
+                write!(f, "{}", "m9".blue().bold())
+            }
+            ChordType::Dominant9 => {
+                write!(f, "{}", "9".yellow().bold())
+            }
+            ChordType::Dominant11 => {
+                write!(f, "{}", "11".yellow().bold())
+            }
+            ChordType::Dominant13 => {
+                write!(f, "{}", "13".yellow().bold())
+            }
+            ChordType::Dominant7Sus4 => {
+                write!(f, "{}", "7sus4".yellow().bold())
+            }
+            ChordType::Dominant7Flat9 => {
+                write!(f, "{}", "7b9".red().bold())
+            }
+            ChordType::Dominant7Sharp9 => {
+                write!(f, "{}", "7#9".red().bold())
+            }
+            ChordType::Dominant7Sharp11 => {
+                write!(f, "{}", "7#11".red().bold())
+            }
+            ChordType::Dominant7Flat13 => {
+                write!(f, "{}", "7b13".red().bold())
+            }
+            ChordType::Altered => {
+                write!(f, "{}", "7alt".red().bold())
+            }
+            ChordType::MinorMajor7 => {
+                write!(f, "{}", "mM7".blue().bold())
+            }
+            ChordType::Augmented => {
+                write!(f, "{}", "aug".cyan().bold())
+            }
+            ChordType::Augmented7 => {
+                write!(f, "{}", "aug7".cyan().bold())
+            }
+            ChordType::AugmentedMajor7 => {
+                write!(f, "{}", "augM7".cyan().bold())
+            }
         }
     }
 }
 
-#[derive(Debug, Clone)]
-pub(crate) enum Inversion {
-    PianoOriginal,
-    PianoFirst,
-    PianoSecond,
-    PianoThird,
-    GuitarFirst,
-    GuitarSecond,
-    GuitarThird,
-    GuitarFourth,
-    GuitarFifth,
+impl ChordType {
+    pub(crate) fn all_vec() -> Vec<Self> {
+        vec![
+            ChordType::Major,
+            ChordType::Minor,
+            ChordType::Major7,
+            ChordType::Minor7,
+            ChordType::Dominant7,
+            ChordType::HalfDiminished7,
+            ChordType::Diminished7,
+            ChordType::Major6,
+            ChordType::Minor6,
+            ChordType::Major9,
+            ChordType::Minor9,
+            ChordType::Dominant9,
+            ChordType::Dominant11,
+            ChordType::Dominant13,
+            ChordType::Dominant7Sus4,
+            ChordType::Dominant7Flat9,
+            ChordType::Dominant7Sharp9,
+            ChordType::Dominant7Sharp11,
+            ChordType::Dominant7Flat13,
+            ChordType::Altered,
+            ChordType::MinorMajor7,
+            ChordType::Augmented,
+            ChordType::Augmented7,
+            ChordType::AugmentedMajor7,
+        ]
+    }
+
+    /// Intervals above the root, split into the core (root, third, fifth and
+    /// seventh/sixth, which get inverted) and the extensions stacked on top.
+    pub(crate) fn intervals(&self) -> (Vec<Interval>, Vec<Interval>) {
+        use Interval::*;
+        match self {
+            ChordType::Major => (vec![PerfectUnison, MajorThird, PerfectFifth], vec![]),
+            ChordType::Minor => (vec![PerfectUnison, MinorThird, PerfectFifth], vec![]),
+            ChordType::Major7 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MajorSeventh],
+                vec![],
+            ),
+            ChordType::Minor7 => (
+                vec![PerfectUnison, MinorThird, PerfectFifth, MinorSeventh],
+                vec![],
+            ),
+            ChordType::Dominant7 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MinorSeventh],
+                vec![],
+            ),
+            ChordType::HalfDiminished7 => (
+                vec![PerfectUnison, MinorThird, DiminishedFifth, MinorSeventh],
+                vec![],
+            ),
+            ChordType::Diminished7 => (
+                vec![PerfectUnison, MinorThird, DiminishedFifth, MajorSixth],
+                vec![],
+            ),
+            ChordType::Major6 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MajorSixth],
+                vec![],
+            ),
+            ChordType::Minor6 => (
+                vec![PerfectUnison, MinorThird, PerfectFifth, MajorSixth],
+                vec![],
+            ),
+            ChordType::Major9 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MajorSeventh],
+                vec![MajorSecond],
+            ),
+            ChordType::Minor9 => (
+                vec![PerfectUnison, MinorThird, PerfectFifth, MinorSeventh],
+                vec![MajorSecond],
+            ),
+            ChordType::Dominant9 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MinorSeventh],
+                vec![MajorSecond],
+            ),
+            // the 11th takes the place of the major third, as in a 9sus4
+            ChordType::Dominant11 => (
+                vec![PerfectUnison, PerfectFourth, PerfectFifth, MinorSeventh],
+                vec![MajorSecond],
+            ),
+            // the 11th is left out, as it clashes with the major third
+            ChordType::Dominant13 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MinorSeventh],
+                vec![MajorSecond, MajorSixth],
+            ),
+            ChordType::Dominant7Sus4 => (
+                vec![PerfectUnison, PerfectFourth, PerfectFifth, MinorSeventh],
+                vec![],
+            ),
+            ChordType::Dominant7Flat9 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MinorSeventh],
+                vec![MinorSecond],
+            ),
+            ChordType::Dominant7Sharp9 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MinorSeventh],
+                vec![AugmentedSecond],
+            ),
+            ChordType::Dominant7Sharp11 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MinorSeventh],
+                vec![AugmentedFourth],
+            ),
+            ChordType::Dominant7Flat13 => (
+                vec![PerfectUnison, MajorThird, PerfectFifth, MinorSeventh],
+                vec![MinorSixth],
+            ),
+            // 7#9b13: the b13 takes the place of the fifth
+            ChordType::Altered => (
+                vec![PerfectUnison, MajorThird, MinorSixth, MinorSeventh],
+                vec![AugmentedSecond],
+            ),
+            ChordType::MinorMajor7 => (
+                vec![PerfectUnison, MinorThird, PerfectFifth, MajorSeventh],
+                vec![],
+            ),
+            ChordType::Augmented => (vec![PerfectUnison, MajorThird, AugmentedFifth], vec![]),
+            ChordType::Augmented7 => (
+                vec![PerfectUnison, MajorThird, AugmentedFifth, MinorSeventh],
+                vec![],
+            ),
+            ChordType::AugmentedMajor7 => (
+                vec![PerfectUnison, MajorThird, AugmentedFifth, MajorSeventh],
+                vec![],
+            ),
+        }
+    }
+
+    /// Whether the chord has a dominant function (major third and minor seventh).
+    pub(crate) fn is_dominant(&self) -> bool {
+        matches!(
+            self,
+            ChordType::Dominant7
+                | ChordType::Dominant9
+                | ChordType::Dominant11
+                | ChordType::Dominant13
+                | ChordType::Dominant7Sus4
+                | ChordType::Dominant7Flat9
+                | ChordType::Dominant7Sharp9
+                | ChordType::Dominant7Sharp11
+                | ChordType::Dominant7Flat13
+                | ChordType::Altered
+                | ChordType::Augmented7
+        )
+    }
+
+    /// Whether the chord is built on a minor third.
+    pub(crate) fn is_minor(&self) -> bool {
+        matches!(
+            self,
+            ChordType::Minor
+                | ChordType::Minor7
+                | ChordType::HalfDiminished7
+                | ChordType::Diminished7
+                | ChordType::Minor6
+                | ChordType::Minor9
+                | ChordType::MinorMajor7
+        )
+    }
+
+    /// All intervals above the root, core first and extensions after.
+    pub(crate) fn interval_vec(&self) -> Vec<Interval> {
+        let (mut core, extensions) = self.intervals();
+        core.extend(extensions);
+        core
+    }
 }
 
-impl fmt::Display for Inversion {
+/// How the chord tones are spread over the voices, from a close position
+/// whose top voices may be dropped an octave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum VoicingType {
+    Close,          // all voices within an octave
+    Drop2,          // second voice from the top dropped an octave
+    Drop3,          // third voice from the top dropped an octave
+    Drop24,         // second and fourth voices from the top dropped an octave
+    Spread,         // bass alone, the top voice above it, the rest on top
+    RootlessA,      // 3-5-7-9, the 13th standing in for the fifth if there is one
+    RootlessB,      // 7-9-3-5
+    UpperStructure, // dominant tritone with a major triad on the tensions above
+}
+
+impl fmt::Display for VoicingType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Inversion::PianoOriginal => {
-                write!(f, "{}", "p/0".white().bold())
-            }
-            Inversion::PianoFirst => {
-                write!(f, "{}", "p/1".green().bold())
+            VoicingType::Close => {
+                write!(f, "{}", "close".white().bold())
             }
-            Inversion::PianoSecond => {
-                write!(f, "{}", "p/2".blue().bold())
+            VoicingType::Drop2 => {
+                write!(f, "{}", "drop2".green().bold())
             }
-            Inversion::PianoThird => {
-                write!(f, "{}", "p/3".cyan().bold())
+            VoicingType::Drop3 => {
+                write!(f, "{}", "drop3".blue().bold())
             }
-            Inversion::GuitarFirst => {
-                write!(f, "{}", "g/1".green().bold())
+            VoicingType::Drop24 => {
+                write!(f, "{}", "drop24".cyan().bold())
             }
-            Inversion::GuitarSecond => {
-                write!(f, "{}", "g/2".blue().bold())
+            VoicingType::Spread => {
+                write!(f, "{}", "spread".purple().bold())
             }
-            Inversion::GuitarThird => {
-                write!(f, "{}", "g/3".cyan().bold())
+            VoicingType::RootlessA => {
+                write!(f, "{}", "rootlessA".yellow().bold())
             }
-            Inversion::GuitarFourth => {
-                write!(f, "{}", "g/4".purple().bold())
+            VoicingType::RootlessB => {
+                write!(f, "{}", "rootlessB".yellow().bold())
             }
-            Inversion::GuitarFifth => {
-                write!(f, "{}", "g/5".yellow().bold())
+            VoicingType::UpperStructure => {
+                write!(f, "{}", "ust".red().bold())
             }
         }
     }
 }
 
-impl Inversion {
-    pub(crate) fn sample(difficulty: Difficulty) -> anyhow::Result<Self> {
-        // let mut rng_seed = ChaCha8Rng::seed_from_u64(42);
-        let mut rng_seed = rand::thread_rng();
-        let prob = match difficulty {
-            Difficulty::Piano => [1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
-            Difficulty::Guitar => [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0],
+impl VoicingType {
+    /// What the instrument plays when nothing else is chosen.
+    pub(crate) fn default_for(difficulty: &Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Piano => VoicingType::Close,
+            Difficulty::Guitar => VoicingType::Drop2,
+        }
+    }
+
+    /// Voices of the close position dropped to the bottom, counted from the top.
+    fn dropped(&self) -> &'static [usize] {
+        match self {
+            VoicingType::Drop2 => &[2],
+            VoicingType::Drop3 => &[3],
+            VoicingType::Drop24 => &[4, 2],
+            _ => &[],
+        }
+    }
+
+    /// Order the voices of a close position bottom to top. Voicing types that
+    /// pick their own chord tones keep the close position.
+    fn arrange(&self, mut close: Vec<Tone>) -> Vec<Tone> {
+        if !matches!(
+            self,
+            VoicingType::Drop2 | VoicingType::Drop3 | VoicingType::Drop24 | VoicingType::Spread
+        ) {
+            return close;
+        }
+        // triads take their bass again on top, the drops need four voices
+        if close.len() < 4 {
+            close.push(close[0].clone());
+        }
+        if *self == VoicingType::Spread {
+            let top = close.remove(close.len() - 1);
+            close.insert(1, top);
+            return close;
+        }
+        let len = close.len();
+        let mut tones: Vec<Tone> = self
+            .dropped()
+            .iter()
+            .map(|&n| close[len - n].clone())
+            .collect();
+        tones.extend(
+            close
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !self.dropped().contains(&(len - i)))
+                .map(|(_, e)| e),
+        );
+        tones
+    }
+}
+
+/// A voicing type in one of its inversions, the inversion being the chord
+/// tone in the bass of the close position it is derived from.
+#[derive(Debug, Clone)]
+pub(crate) struct Voicing {
+    pub(crate) voicing_type: VoicingType,
+    pub(crate) inversion: usize,
+    floor: u8, // lowest note the voicing is built from
+}
+
+impl fmt::Display for Voicing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.voicing_type, self.inversion)
+    }
+}
+
+impl Voicing {
+    pub(crate) fn new(
+        voicing_type: VoicingType,
+        inversion: usize,
+        difficulty: &Difficulty,
+    ) -> Self {
+        let floor = match difficulty {
+            Difficulty::Piano => pitch::PIANO_FLOOR,
+            Difficulty::Guitar => pitch::GUITAR_FLOOR,
         };
+        Voicing {
+            voicing_type,
+            inversion,
+            floor,
+        }
+    }
 
-        let mnm = Categorical::new(&prob)?;
-        Ok(match mnm.sample(&mut rng_seed) as i32 {
-            0 => Inversion::PianoOriginal,
-            1 => Inversion::PianoFirst,
-            2 => Inversion::PianoSecond,
-            3 => Inversion::PianoThird,
-            4 => Inversion::GuitarFirst,
-            5 => Inversion::GuitarSecond,
-            6 => Inversion::GuitarThird,
-            7 => Inversion::GuitarFourth,
-            8 => Inversion::GuitarFifth,
-            _ => panic!("random error"),
-        })
+    /// A random inversion of the instrument's usual voicing type.
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
+        let mut rng_seed = rand::thread_rng();
+        let mnm = Categorical::new(&[1.0, 1.0, 1.0, 1.0])?;
+        let inversion = mnm.sample(&mut rng_seed) as usize;
+        Ok(Self::new(
+            VoicingType::default_for(&difficulty),
+            inversion,
+            &difficulty,
+        ))
+    }
+
+    /// Every inversion of the voicing type; a triad has three, so the fourth
+    /// voices it in root position again. The rootless voicings take turns
+    /// between A and B instead, and an upper structure has only one.
+    pub(crate) fn all_vec(voicing_type: &VoicingType, difficulty: &Difficulty) -> Vec<Self> {
+        match voicing_type {
+            VoicingType::RootlessA | VoicingType::RootlessB => vec![
+                Self::new(VoicingType::RootlessA, 0, difficulty),
+                Self::new(VoicingType::RootlessB, 0, difficulty),
+            ],
+            VoicingType::UpperStructure => vec![Self::new(voicing_type.clone(), 0, difficulty)],
+            _ => (0..4)
+                .map(|e| Self::new(voicing_type.clone(), e, difficulty))
+                .collect(),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct Chord {
     pub(crate) tonic: Tone,
-    chord_type: ChordType,
-    inversion: Inversion,
+    pub(crate) chord_type: ChordType,
+    pub(crate) layout: Voicing,
     pub(crate) tones: Vec<Tone>,
+    pub(crate) voicing: Vec<Pitch>, // tones in register, bottom to top
 }
 
 impl Chord {
-    pub(crate) fn new(mut tonic: Tone, chord_type: ChordType, inversion: Inversion) -> Self {
+    pub(crate) fn new(
+        mut tonic: Tone,
+        chord_type: ChordType,
+        layout: Voicing,
+    ) -> Result<Self, TheoryError> {
         info!("Chord::new(): build {}{}", tonic, chord_type);
         match chord_type {
             ChordType::Diminished7 => {
@@ -137,134 +476,306 @@ impl Chord {
                 tonic = tonic.rematch_chord(&chord_type);
             }
         };
-        let (third, fifth, seventh) = match chord_type {
-            ChordType::Major7 => (
-                tonic.add_interval(Interval::MajorThird),
-                tonic.add_interval(Interval::PerfectFifth),
-                tonic.add_interval(Interval::MajorSeventh),
-            ),
-            ChordType::Minor7 => (
-                tonic.add_interval(Interval::MinorThird),
-                tonic.add_interval(Interval::PerfectFifth),
-                tonic.add_interval(Interval::MinorSeventh),
-            ),
-            ChordType::Dominant7 => (
-                tonic.add_interval(Interval::MajorThird),
-                tonic.add_interval(Interval::PerfectFifth),
-                tonic.add_interval(Interval::MinorSeventh),
-            ),
-            ChordType::HalfDiminished7 => (
-                tonic.add_interval(Interval::MinorThird),
-                tonic.add_interval(Interval::DiminishedFifth),
-                tonic.add_interval(Interval::MinorSeventh),
-            ),
-            ChordType::Diminished7 => (
-                tonic.add_interval(Interval::MinorThird),
-                tonic.add_interval(Interval::DiminishedFifth),
-                tonic.add_interval(Interval::MajorSixth),
-            ),
-        };
+        Self::spelled(tonic, chord_type, layout)
+    }
 
-        let tones = match inversion {
-            Inversion::PianoOriginal => {
-                vec![tonic.clone(), third, fifth, seventh]
-            }
-            Inversion::PianoFirst => {
-                vec![third, fifth, seventh, tonic.clone()]
-            }
-            Inversion::PianoSecond => {
-                vec![fifth, seventh, tonic.clone(), third]
-            }
-            Inversion::PianoThird => {
-                vec![seventh, tonic.clone(), third, fifth]
-            }
-            Inversion::GuitarFirst => {
-                vec![
-                    tonic.clone(),
-                    fifth.clone(),
-                    seventh,
-                    third,
-                    fifth,
-                    tonic.clone(),
-                ]
-            }
-            Inversion::GuitarSecond => {
-                vec![
-                    fifth.clone(),
-                    tonic.clone(),
-                    fifth.clone(),
-                    seventh,
-                    third,
-                    fifth,
-                ]
-            }
-            Inversion::GuitarThird => {
-                vec![fifth.clone(), tonic.clone(), fifth, seventh, third]
-            }
-            Inversion::GuitarFourth => {
-                vec![seventh.clone(), third, fifth, tonic.clone(), seventh]
-            }
-            Inversion::GuitarFifth => {
-                vec![seventh, third, fifth, tonic.clone()]
+    /// Build the chord on the tonic as spelled, without rematching it first.
+    /// Fails when a chord tone would need more than a double accidental.
+    pub(crate) fn spelled(
+        tonic: Tone,
+        chord_type: ChordType,
+        layout: Voicing,
+    ) -> Result<Self, TheoryError> {
+        let (core_intervals, extension_intervals) = chord_type.intervals();
+        let core = core_intervals
+            .into_iter()
+            .map(|e| tonic.add_interval(e))
+            .collect::<Result<Vec<Tone>, TheoryError>>()?;
+        let extensions = extension_intervals
+            .into_iter()
+            .map(|e| tonic.add_interval(e))
+            .collect::<Result<Vec<Tone>, TheoryError>>()?;
+
+        let jazz = Self::jazz_tones(&tonic, &chord_type, &layout.voicing_type)?;
+        let inversion = match jazz {
+            Some(_) => 0,
+            None => layout.inversion % core.len(),
+        };
+        let tones = match jazz {
+            Some(tones) => tones,
+            None => {
+                let mut tones = layout.voicing_type.arrange(Self::rotate(&core, inversion));
+                // extensions replace doubled tones from the top, or go on top
+                for extension in extensions.into_iter() {
+                    let doubled = (1..tones.len())
+                        .rev()
+                        .find(|&i| tones[..i].contains(&tones[i]));
+                    match doubled {
+                        Some(i) => tones[i] = extension,
+                        None => tones.push(extension),
+                    }
+                }
+                tones
             }
         };
-        Chord {
+
+        let voicing = Pitch::stack(&tones, layout.floor);
+        Ok(Chord {
             tonic,
             chord_type,
-            inversion,
+            layout: Voicing {
+                inversion,
+                ..layout
+            },
             tones,
+            voicing,
+        })
+    }
+
+    /// The same chord in another voicing, stacked up from the given floor.
+    pub(crate) fn revoiced(&self, layout: Voicing, floor: u8) -> Result<Chord, TheoryError> {
+        let mut chord = Self::spelled(self.tonic.clone(), self.chord_type.clone(), layout)?;
+        chord.voicing = Pitch::stack(&chord.tones, floor);
+        Ok(chord)
+    }
+
+    /// The same chord and inversion in another voicing type.
+    pub(crate) fn restyled(&self, voicing_type: VoicingType) -> Result<Chord, TheoryError> {
+        let layout = Voicing {
+            voicing_type,
+            ..self.layout.clone()
+        };
+        Self::spelled(self.tonic.clone(), self.chord_type.clone(), layout)
+    }
+
+    /// Rootless and upper-structure voicings, bottom to top, built from the
+    /// chord's own tensions. None for a triad, which has no seventh to keep
+    /// once the root is gone, and for the other voicing types. An upper
+    /// structure falls back to rootless A on chords that are not dominants.
+    fn jazz_tones(
+        tonic: &Tone,
+        chord_type: &ChordType,
+        voicing_type: &VoicingType,
+    ) -> Result<Option<Vec<Tone>>, TheoryError> {
+        use Interval::*;
+        let (core, extensions) = chord_type.intervals();
+        if core.len() < 4 {
+            return Ok(None);
         }
+        let third = tonic.add_interval(core[1].clone())?;
+        let seventh = tonic.add_interval(core[3].clone())?;
+        let ninth = extensions
+            .iter()
+            .find(|e| matches!(e, MinorSecond | MajorSecond | AugmentedSecond))
+            .cloned()
+            .unwrap_or(MajorSecond);
+        let fifth = extensions
+            .iter()
+            .find(|e| matches!(e, AugmentedFourth | MinorSixth | MajorSixth))
+            .cloned()
+            .unwrap_or(core[2].clone());
+        let ninth = tonic.add_interval(ninth)?;
+        let fifth = tonic.add_interval(fifth)?;
+
+        let upper = match chord_type {
+            ChordType::Dominant7
+            | ChordType::Dominant9
+            | ChordType::Dominant13
+            | ChordType::Dominant7Sharp11 => Some(MajorSecond), // II: 9, #11, 13
+            ChordType::Dominant7Flat9 => Some(MajorSixth), // VI: 13, b9, 3
+            ChordType::Dominant7Sharp9 => Some(MinorThird), // bIII: #9, 5, b7
+            ChordType::Altered => Some(MinorSixth),        // bVI: b13, 1, #9
+            _ => None,
+        };
+        Ok(match (voicing_type, upper) {
+            (VoicingType::RootlessA, _) | (VoicingType::UpperStructure, None) => {
+                Some(vec![third, fifth, seventh, ninth])
+            }
+            (VoicingType::RootlessB, _) => Some(vec![seventh, ninth, third, fifth]),
+            (VoicingType::UpperStructure, Some(interval)) => {
+                let root = tonic.add_interval(interval)?;
+                let mut tones = vec![third, seventh];
+                for interval in [PerfectUnison, MajorThird, PerfectFifth] {
+                    tones.push(root.add_interval(interval)?);
+                }
+                Some(tones)
+            }
+            _ => None,
+        })
     }
 
-    fn gen_diminished(&self, difficulty: Difficulty) -> anyhow::Result<Chord> {
+    /// Whether the played notes, lowest first, sound this chord: the same
+    /// tones from the bottom up, in any octave and spread. A seventh chord
+    /// is also heard in its rootless and upper-structure voicings.
+    pub(crate) fn matches(&self, played: &[Pitch]) -> bool {
+        let sounds = |tones: &[Tone]| {
+            played.len() == tones.len()
+                && played
+                    .iter()
+                    .zip(tones.iter())
+                    .all(|(p, t)| p.class() == t.idx)
+        };
+        sounds(&self.tones)
+            || [
+                VoicingType::RootlessA,
+                VoicingType::RootlessB,
+                VoicingType::UpperStructure,
+            ]
+            .iter()
+            .filter_map(|e| {
+                Self::jazz_tones(&self.tonic, &self.chord_type, e)
+                    .ok()
+                    .flatten()
+            })
+            .any(|tones| sounds(&tones))
+    }
+
+    fn rotate(core: &[Tone], n: usize) -> Vec<Tone> {
+        let mut tones = core.to_vec();
+        tones.rotate_left(n % core.len());
+        tones
+    }
+
+    fn gen_diminished(&self, difficulty: Difficulty) -> Result<Chord, TheoryError> {
         let matched_tonic = self.tonic.clone().rematch_diminished();
-        Ok(Chord::new(
+        Chord::new(
             matched_tonic,
             ChordType::Diminished7,
-            Inversion::sample(difficulty)?,
-        ))
+            Voicing::sample(difficulty)?,
+        )
     }
 
-    pub(crate) fn gen_secondary_dominant(&self, difficulty: Difficulty) -> anyhow::Result<Chord> {
+    pub(crate) fn gen_secondary_dominant(
+        &self,
+        difficulty: Difficulty,
+    ) -> Result<Chord, TheoryError> {
         let chord_type = ChordType::Dominant7;
         let matched_tonic = self.tonic.clone().rematch_chord(&chord_type);
-        Ok(Chord::new(
-            matched_tonic.add_interval(Interval::PerfectFifth),
+        Chord::new(
+            matched_tonic.add_interval(Interval::PerfectFifth)?,
             chord_type,
-            Inversion::sample(difficulty)?,
-        ))
+            Voicing::sample(difficulty)?,
+        )
     }
 
-    pub(crate) fn gen_substitute_sd(&self, difficulty: Difficulty) -> anyhow::Result<Chord> {
+    pub(crate) fn gen_substitute_sd(&self, difficulty: Difficulty) -> Result<Chord, TheoryError> {
         let chord_type = ChordType::Dominant7;
         let matched_tonic = self.tonic.clone().rematch_chord(&chord_type);
-        Ok(Chord::new(
-            matched_tonic.add_interval(Interval::MajorSecond),
+        Chord::new(
+            matched_tonic.add_interval(Interval::MajorSecond)?,
             chord_type,
-            Inversion::sample(difficulty)?,
-        ))
+            Voicing::sample(difficulty)?,
+        )
+    }
+
+    /// Tritone substitute of the dominant resolving to this chord: a dominant
+    /// seventh a minor second above it, e.g. Db7 for C.
+    pub(crate) fn gen_tritone_sub(&self, difficulty: Difficulty) -> Result<Chord, TheoryError> {
+        self.gen_on(Interval::MinorSecond, ChordType::Dominant7, difficulty)
+    }
+
+    /// Backdoor dominant of this chord: a dominant seventh a whole step below, e.g. Bb7 for C.
+    pub(crate) fn gen_backdoor_dominant(
+        &self,
+        difficulty: Difficulty,
+    ) -> Result<Chord, TheoryError> {
+        self.gen_on(Interval::MinorSeventh, ChordType::Dominant7, difficulty)
+    }
+
+    /// Minor subdominant of this chord, e.g. Fm7 (or Fm6) for C.
+    pub(crate) fn gen_minor_subdominant(
+        &self,
+        chord_type: ChordType,
+        difficulty: Difficulty,
+    ) -> Result<Chord, TheoryError> {
+        self.gen_on(Interval::PerfectFourth, chord_type, difficulty)
     }
 
-    pub(crate) fn gen_second_minor(&self, difficulty: Difficulty) -> anyhow::Result<Chord> {
+    /// Minor seventh chord a whole step above this chord, its ii, e.g. Dm7 for C.
+    pub(crate) fn gen_supertonic_minor(
+        &self,
+        difficulty: Difficulty,
+    ) -> Result<Chord, TheoryError> {
+        self.gen_on(Interval::MajorSecond, ChordType::Minor7, difficulty)
+    }
+
+    /// Chord of the given type on a root the interval above this chord's root.
+    fn gen_on(
+        &self,
+        interval: Interval,
+        chord_type: ChordType,
+        difficulty: Difficulty,
+    ) -> Result<Chord, TheoryError> {
+        let matched_tonic = self.tonic.clone().rematch_chord(&chord_type);
+        Chord::new(
+            matched_tonic.add_interval(interval)?,
+            chord_type,
+            Voicing::sample(difficulty)?,
+        )
+    }
+
+    pub(crate) fn gen_second_minor(&self, difficulty: Difficulty) -> Result<Chord, TheoryError> {
         let chord_type = ChordType::Minor7;
         let matched_tonic = self.tonic.clone().rematch_chord(&chord_type);
-        Ok(Chord::new(
-            matched_tonic.add_interval(Interval::PerfectFifth),
+        Chord::new(
+            matched_tonic.add_interval(Interval::PerfectFifth)?,
             chord_type,
-            Inversion::sample(difficulty)?,
-        ))
+            Voicing::sample(difficulty)?,
+        )
+    }
+
+    /// Major keys whose dominant seventh holds one of this chord's tones as its
+    /// root, third, fifth or seventh, paired with that common tone.
+    pub(crate) fn gen_common_tone_keys(&self) -> Vec<(Tone, Key)> {
+        let mut tones: Vec<Tone> = Vec::new();
+        for tone in self.tones.iter() {
+            if !tones.contains(tone) {
+                tones.push(tone.clone());
+            }
+        }
+        let mut keys = Vec::new();
+        for tone in tones.into_iter() {
+            for semitones in [7, 11, 2, 5] {
+                let idx = (tone.idx - 1 - semitones).rem_euclid(12) + 1;
+                keys.push((tone.clone(), Key::new(Tone::from_idx(idx), KeyType::Ionian)));
+            }
+        }
+        keys
     }
 
     pub(crate) fn gen_major_keys(&self) -> Vec<Key> {
         let int_tonic_vec: Vec<Interval> = match self.chord_type {
-            ChordType::Major7 => Vec::from([Interval::PerfectUnison, Interval::PerfectFifth]),
-            ChordType::Minor7 => Vec::from([
+            ChordType::Major => Vec::from([
+                Interval::PerfectUnison,
+                Interval::PerfectFifth,
+                Interval::PerfectFourth,
+            ]),
+            ChordType::Major7 | ChordType::Major6 | ChordType::Major9 => {
+                Vec::from([Interval::PerfectUnison, Interval::PerfectFifth])
+            }
+            ChordType::Minor | ChordType::Minor7 | ChordType::MinorMajor7 => Vec::from([
                 Interval::MinorSeventh,
                 Interval::MinorSixth,
                 Interval::MinorThird,
             ]),
-            ChordType::Dominant7 => Vec::from([Interval::PerfectFourth]),
+            ChordType::Minor6 => Vec::from([Interval::MinorSeventh]),
+            ChordType::Minor9 => Vec::from([Interval::MinorSeventh, Interval::MinorThird]),
+            // altered dominants are not diatonic to a major key, use the key they resolve to
+            ChordType::Dominant7
+            | ChordType::Dominant9
+            | ChordType::Dominant11
+            | ChordType::Dominant13
+            | ChordType::Dominant7Sus4
+            | ChordType::Dominant7Flat9
+            | ChordType::Dominant7Sharp9
+            | ChordType::Dominant7Sharp11
+            | ChordType::Dominant7Flat13
+            | ChordType::Altered => Vec::from([Interval::PerfectFourth]),
             ChordType::HalfDiminished7 => Vec::from([Interval::MinorSecond]),
+            // III+ of a harmonic minor, on the tonic of its relative major
+            ChordType::Augmented | ChordType::Augmented7 | ChordType::AugmentedMajor7 => {
+                Vec::from([Interval::PerfectUnison])
+            }
             ChordType::Diminished7 => Vec::from([
                 Interval::MinorSecond,
                 Interval::MajorThird,
@@ -276,11 +787,13 @@ impl Chord {
         let mut keys = Vec::new();
         for int_tonic in int_tonic_vec.into_iter() {
             let matched_tonic = self.tonic.clone().rematch_interval(&int_tonic);
-            let new_tonic = matched_tonic.add_interval(int_tonic);
+            // a tonic beyond a double accidental is no key to move to
+            let Ok(new_tonic) = matched_tonic.add_interval(int_tonic) else {
+                continue;
+            };
 
-            let key_type = KeyType::Ionian;
-            let matched_new_tonic = new_tonic.clone().rematch_key(&key_type);
-            keys.push(Key::new(new_tonic, key_type));
+            // Key::new respells the tonic as the key would
+            keys.push(Key::new(new_tonic, KeyType::Ionian));
         }
         keys
     }
@@ -291,7 +804,7 @@ impl fmt::Display for Chord {
         write!(
             f,
             "{}{}-{}: {:?}",
-            self.tonic, self.chord_type, self.inversion, self.tones
+            self.tonic, self.chord_type, self.layout, self.tones
         )
     }
 }
@@ -301,7 +814,7 @@ impl fmt::Debug for Chord {
         write!(
             f,
             "{}{}-{}: {:?}",
-            self.tonic, self.chord_type, self.inversion, self.tones
+            self.tonic, self.chord_type, self.layout, self.tones
         )
     }
 }
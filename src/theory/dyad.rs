@@ -0,0 +1,222 @@
+use colored::*;
+use log::info;
+use rand::prelude::*;
+use std::{collections::HashMap, fmt, time::Duration};
+
+use crate::app::Difficulty;
+use super::{
+    error::TheoryError,
+    key::Key,
+    pitch::Pitch,
+    tone::{Interval, Tone},
+};
+
+/// Which way the interval goes from the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Direction {
+    Above,
+    Below,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Above => {
+                write!(f, "above")
+            }
+            Direction::Below => {
+                write!(f, "below")
+            }
+        }
+    }
+}
+
+/// An interval to play from a named root, e.g. a minor 6th above E. The root
+/// may be played in any octave, the other note has to be the interval away
+/// from it in that register.
+#[derive(Debug, Clone)]
+pub(crate) struct Dyad {
+    pub(crate) root: Tone,
+    pub(crate) interval: Interval,
+    pub(crate) direction: Direction,
+}
+
+impl fmt::Display for Dyad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.interval.name().bold(),
+            self.direction,
+            self.root
+        )
+    }
+}
+
+impl Dyad {
+    /// Intervals drilled, from the smallest: every spelled interval within
+    /// the octave, so that the staff tells an augmented 5th from a minor 6th.
+    pub(crate) fn interval_vec() -> Vec<Interval> {
+        use Interval::*;
+        vec![
+            MinorSecond,
+            MajorSecond,
+            AugmentedSecond,
+            MinorThird,
+            MajorThird,
+            DiminishedFourth,
+            PerfectFourth,
+            AugmentedFourth,
+            DiminishedFifth,
+            PerfectFifth,
+            AugmentedFifth,
+            MinorSixth,
+            MajorSixth,
+            DiminishedSeventh,
+            MinorSeventh,
+            MajorSeventh,
+        ]
+    }
+
+    /// A random interval either way from the tonic of a sampled key, so that
+    /// the root is spelled as a key would spell it. Intervals whose other note
+    /// needs more than a double accidental are left out.
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
+        let mut rng = rand::thread_rng();
+        let root = Key::sample(difficulty)?.tonic;
+        let direction = match rng.gen_bool(0.5) {
+            true => Direction::Above,
+            false => Direction::Below,
+        };
+        let dyads: Vec<Dyad> = Self::interval_vec()
+            .into_iter()
+            .map(|interval| Dyad {
+                root: root.clone(),
+                interval,
+                direction,
+            })
+            .filter(|e| e.other().is_ok())
+            .collect();
+        let dyad = dyads
+            .choose(&mut rng)
+            .cloned()
+            .ok_or(TheoryError::NoCandidate("interval"))?;
+        info!("Dyad::sample(): {} sampled", dyad);
+        Ok(dyad)
+    }
+
+    /// The other note of the interval, spelled from the root.
+    pub(crate) fn other(&self) -> Result<Tone, TheoryError> {
+        match self.direction {
+            Direction::Above => self.root.add_interval(self.interval.clone()),
+            Direction::Below => self.root.minus_interval(self.interval.clone()),
+        }
+    }
+
+    /// The two notes played, spelled and lowest first, when they are the
+    /// root and the note exactly the interval away from it that way.
+    pub(crate) fn matches(&self, played: &[Pitch]) -> Option<Vec<Pitch>> {
+        let [low, high] = played else {
+            return None;
+        };
+        let (low, high) = match low.midi <= high.midi {
+            true => (low, high),
+            false => (high, low),
+        };
+        if (high.midi - low.midi) as i8 != self.interval.key_diff() {
+            return None;
+        }
+        let other = self.other().ok()?;
+        let (low_tone, high_tone) = match self.direction {
+            Direction::Above => (self.root.clone(), other),
+            Direction::Below => (other, self.root.clone()),
+        };
+        if low.class() != low_tone.idx || high.class() != high_tone.idx {
+            return None;
+        }
+        Some(vec![
+            Pitch {
+                midi: low.midi,
+                tone: low_tone,
+            },
+            Pitch {
+                midi: high.midi,
+                tone: high_tone,
+            },
+        ])
+    }
+
+    /// What was wrong with two notes that do not match.
+    pub(crate) fn miss(&self, played: &[Pitch]) -> String {
+        let semitones = match played {
+            [low, high] => high.midi.abs_diff(low.midi),
+            _ => 0,
+        };
+        let root = played.iter().any(|e| e.class() == self.root.idx);
+        match (root, semitones as i8 == self.interval.key_diff()) {
+            (false, _) => format!("no {} played", self.root),
+            (true, true) => format!("{} is the other way round", self.interval.name()),
+            (true, false) => format!(
+                "{} semitones apart, {} expected",
+                semitones,
+                self.interval.key_diff()
+            ),
+        }
+    }
+}
+
+/// Answers to one interval in one direction, one per question asked.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tally {
+    pub(crate) hits: u32,
+    pub(crate) misses: u32,
+    pub(crate) time: Duration, // to the hits, summed up
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.hits, self.hits + self.misses)?;
+        if self.hits > 0 {
+            write!(f, " in {:.1}s", self.time.as_secs_f32() / self.hits as f32)?;
+        }
+        Ok(())
+    }
+}
+
+/// Answers of the interval drill, per interval and direction.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IntervalStats {
+    tallies: HashMap<(Interval, Direction), Tally>,
+}
+
+impl IntervalStats {
+    pub(crate) fn hit(&mut self, dyad: &Dyad, time: Duration) {
+        let tally = self.tally(dyad);
+        tally.hits += 1;
+        tally.time += time;
+    }
+
+    pub(crate) fn miss(&mut self, dyad: &Dyad) {
+        self.tally(dyad).misses += 1;
+    }
+
+    fn tally(&mut self, dyad: &Dyad) -> &mut Tally {
+        self.tallies
+            .entry((dyad.interval.clone(), dyad.direction))
+            .or_default()
+    }
+
+    /// Tallies of the intervals asked so far, from the smallest, the one
+    /// above before the one below.
+    pub(crate) fn rows(&self) -> Vec<(Interval, Direction, Tally)> {
+        let mut rows = Vec::new();
+        for interval in Dyad::interval_vec().into_iter() {
+            for direction in [Direction::Above, Direction::Below] {
+                if let Some(tally) = self.tallies.get(&(interval.clone(), direction)) {
+                    rows.push((interval.clone(), direction, tally.clone()));
+                }
+            }
+        }
+        rows
+    }
+}
@@ -0,0 +1,67 @@
+use statrs::StatsError;
+use std::{error::Error, fmt};
+
+/// Errors of the theory functions, so that a bad degree or a failed draw
+/// reaches the caller instead of tearing down the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TheoryError {
+    InvalidDegree(i8),
+    InvalidMode(i8),
+    UnknownQuality { key: String, degree: i8 },
+    Distribution(String),
+    Sample { what: &'static str, idx: i32 },
+    NoCandidate(&'static str),
+    NotTriadic(String),
+    UnknownNumeral(String),
+    Unspellable(String),
+}
+
+impl fmt::Display for TheoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TheoryError::InvalidDegree(idx) => {
+                write!(f, "degree {} is not valid, expected 1-7", idx)
+            }
+            TheoryError::InvalidMode(idx) => {
+                write!(f, "mode {} is not valid, expected 0-6", idx)
+            }
+            TheoryError::UnknownQuality { key, degree } => {
+                write!(
+                    f,
+                    "degree {} of {} does not stack into a known seventh chord",
+                    degree, key
+                )
+            }
+            TheoryError::Distribution(s) => {
+                write!(f, "invalid sampling weights: {}", s)
+            }
+            TheoryError::Sample { what, idx } => {
+                write!(f, "sampled {} out of range: {}", what, idx)
+            }
+            TheoryError::NoCandidate(what) => {
+                write!(f, "no {} to choose from", what)
+            }
+            TheoryError::NotTriadic(chord) => {
+                write!(f, "{} is not built on a major or minor triad", chord)
+            }
+            TheoryError::UnknownNumeral(numeral) => {
+                write!(
+                    f,
+                    "'{}' is not a roman numeral (e.g. ii7, bVI7, #iv°7)",
+                    numeral
+                )
+            }
+            TheoryError::Unspellable(tone) => {
+                write!(f, "the {} needs more than a double accidental", tone)
+            }
+        }
+    }
+}
+
+impl Error for TheoryError {}
+
+impl From<StatsError> for TheoryError {
+    fn from(e: StatsError) -> Self {
+        TheoryError::Distribution(e.to_string())
+    }
+}
@@ -0,0 +1,458 @@
+use colored::*;
+use log::debug;
+use std::fmt;
+
+use crate::app::Difficulty;
+use super::{
+    chord::{Chord, Voicing, VoicingType},
+    pitch::Pitch,
+    tone::Tone,
+    voice_leading,
+};
+
+/// Highest fret reachable on the neck.
+pub(crate) const FRETS: u8 = 22;
+/// Widest span between the lowest and highest fretted note a hand can hold.
+pub(crate) const MAX_STRETCH: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Tuning {
+    Standard,
+    DropD,
+    Dadgad,
+    OpenG,
+    SevenString, // standard with a low B
+    Bass,        // four strings
+    FiveStringBass,
+    Ukulele, // re-entrant, the G string sits above the C
+}
+
+impl fmt::Display for Tuning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tuning::Standard => {
+                write!(f, "{}", "standard".white().bold())
+            }
+            Tuning::DropD => {
+                write!(f, "{}", "drop D".green().bold())
+            }
+            Tuning::Dadgad => {
+                write!(f, "{}", "DADGAD".blue().bold())
+            }
+            Tuning::OpenG => {
+                write!(f, "{}", "open G".cyan().bold())
+            }
+            Tuning::SevenString => {
+                write!(f, "{}", "7-string".purple().bold())
+            }
+            Tuning::Bass => {
+                write!(f, "{}", "bass".red().bold())
+            }
+            Tuning::FiveStringBass => {
+                write!(f, "{}", "5-string bass".red().bold())
+            }
+            Tuning::Ukulele => {
+                write!(f, "{}", "ukulele".yellow().bold())
+            }
+        }
+    }
+}
+
+impl Tuning {
+    pub(crate) fn all_vec() -> Vec<Self> {
+        vec![
+            Tuning::Standard,
+            Tuning::DropD,
+            Tuning::Dadgad,
+            Tuning::OpenG,
+            Tuning::SevenString,
+            Tuning::Bass,
+            Tuning::FiveStringBass,
+            Tuning::Ukulele,
+        ]
+    }
+
+    /// Open strings as MIDI notes, lowest string first.
+    pub(crate) fn strings(&self) -> Vec<u8> {
+        match self {
+            Tuning::Standard => vec![40, 45, 50, 55, 59, 64], // E2 A2 D3 G3 B3 E4
+            Tuning::DropD => vec![38, 45, 50, 55, 59, 64],    // D2 A2 D3 G3 B3 E4
+            Tuning::Dadgad => vec![38, 45, 50, 55, 57, 62],   // D2 A2 D3 G3 A3 D4
+            Tuning::OpenG => vec![38, 43, 50, 55, 59, 62],    // D2 G2 D3 G3 B3 D4
+            Tuning::SevenString => vec![35, 40, 45, 50, 55, 59, 64], // B1 E2 A2 D3 G3 B3 E4
+            Tuning::Bass => vec![28, 33, 38, 43],             // E1 A1 D2 G2
+            Tuning::FiveStringBass => vec![23, 28, 33, 38, 43], // B0 E1 A1 D2 G2
+            Tuning::Ukulele => vec![67, 60, 64, 69],          // G4 C4 E4 A4
+        }
+    }
+
+    /// Notes a voicing can take, from the lowest open string up to an
+    /// octave above the highest, as far as most players reach.
+    pub(crate) fn range(&self) -> (u8, u8) {
+        let strings = self.strings();
+        let low = strings.iter().min().copied().unwrap_or(0);
+        let high = strings.iter().max().copied().unwrap_or(0);
+        (low, high + 12)
+    }
+}
+
+/// Open chord shape a fingering is moved from, named after the string and
+/// finger holding its lowest root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CagedShape {
+    C, // root on the fifth string, under the ring finger
+    A, // root on the fifth string, under the barre
+    G, // root on the sixth string, under the ring finger
+    E, // root on the sixth string, under the barre
+    D, // root on the fourth string
+}
+
+impl fmt::Display for CagedShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CagedShape::C => {
+                write!(f, "{}", "C".white().bold())
+            }
+            CagedShape::A => {
+                write!(f, "{}", "A".green().bold())
+            }
+            CagedShape::G => {
+                write!(f, "{}", "G".blue().bold())
+            }
+            CagedShape::E => {
+                write!(f, "{}", "E".cyan().bold())
+            }
+            CagedShape::D => {
+                write!(f, "{}", "D".purple().bold())
+            }
+        }
+    }
+}
+
+/// A chord voicing laid out on the strings of a guitar.
+#[derive(Debug, Clone)]
+pub(crate) struct Fingering {
+    pub(crate) tuning: Tuning,
+    pub(crate) frets: Vec<Option<u8>>, // per string, lowest first, None when muted
+    pub(crate) pitches: Vec<Pitch>,    // sounded notes, bottom to top
+    pub(crate) shape: Option<CagedShape>,
+}
+
+impl fmt::Display for Fingering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frets: Vec<String> = self
+            .frets
+            .iter()
+            .map(|e| match e {
+                Some(fret) => fret.to_string(),
+                None => "x".to_string(),
+            })
+            .collect();
+        match &self.shape {
+            Some(shape) => write!(f, "{} ({} shape)", frets.join("-"), shape),
+            None => write!(f, "{}", frets.join("-")),
+        }
+    }
+}
+
+impl Fingering {
+    /// Every playable fingering of the tones, bottom to top on strings of
+    /// rising open pitch, each tone in any octave, within the stretch of one
+    /// hand.
+    pub(crate) fn all(tones: &[Tone], tonic: &Tone, tuning: &Tuning) -> Vec<Self> {
+        let strings = tuning.strings();
+        // re-entrant strings are searched in the order of their open pitch
+        let mut order: Vec<usize> = (0..strings.len()).collect();
+        order.sort_by_key(|&i| strings[i]);
+        let mut found: Vec<Vec<(usize, u8)>> = Vec::new();
+        Self::place(tones, &strings, &order, &mut Vec::new(), &mut found);
+        found
+            .into_iter()
+            .map(|placed| {
+                let mut frets: Vec<Option<u8>> = vec![None; strings.len()];
+                for (string, fret) in placed.iter() {
+                    frets[*string] = Some(*fret);
+                }
+                let pitches: Vec<Pitch> = placed
+                    .iter()
+                    .zip(tones.iter())
+                    .map(|((string, fret), tone)| Pitch {
+                        midi: strings[*string] + fret,
+                        tone: tone.clone(),
+                    })
+                    .collect();
+                let shape = match tuning {
+                    Tuning::Standard => Self::caged(&placed, &strings, tonic),
+                    _ => None,
+                };
+                Fingering {
+                    tuning: tuning.clone(),
+                    frets,
+                    pitches,
+                    shape,
+                }
+            })
+            .collect()
+    }
+
+    /// The fingering closest to the chord as voiced, preferring adjacent
+    /// strings and a small stretch. A chord with more tones than the strings
+    /// can hold is thinned out first. None when it cannot be played at all.
+    pub(crate) fn of(chord: &Chord, tuning: &Tuning) -> Option<Self> {
+        let best = Self::thinned(chord).into_iter().find_map(|tones| {
+            Self::all(&tones, &chord.tonic, tuning)
+                .into_iter()
+                .min_by_key(|e| e.cost(&chord.voicing))
+        });
+        debug!(
+            "Fingering::of(): {:?} fingered {}",
+            chord.voicing,
+            best.as_ref()
+                .map(|e| e.to_string())
+                .unwrap_or("nowhere".to_string())
+        );
+        best
+    }
+
+    /// Lowest fretted note, the fret the hand sits at; 0 for open strings only.
+    pub(crate) fn position(&self) -> u8 {
+        self.fretted().min().unwrap_or(0)
+    }
+
+    /// Frets between the lowest and the highest fretted note.
+    pub(crate) fn stretch(&self) -> u8 {
+        match (self.fretted().min(), self.fretted().max()) {
+            (Some(low), Some(high)) => high - low,
+            _ => 0,
+        }
+    }
+
+    fn fretted(&self) -> impl Iterator<Item = u8> + '_ {
+        self.frets.iter().flatten().copied().filter(|&e| e > 0)
+    }
+
+    /// Distance from the voicing, plus the effort of the fingering.
+    fn cost(&self, voicing: &[Pitch]) -> u32 {
+        let distance: u32 = self
+            .pitches
+            .iter()
+            .filter_map(|a| {
+                voicing
+                    .iter()
+                    .filter(|b| b.class() == a.class())
+                    .map(|b| (a.midi as i16 - b.midi as i16).unsigned_abs() as u32)
+                    .min()
+            })
+            .sum();
+        distance + self.effort()
+    }
+
+    /// Muted strings inside the chord and the stretch, which both make it
+    /// harder to play.
+    fn effort(&self) -> u32 {
+        let mut sounded: Vec<u8> = (0..self.frets.len())
+            .filter(|&i| self.frets[i].is_some())
+            .map(|i| self.tuning.strings()[i])
+            .collect();
+        sounded.sort();
+        let all = self.tuning.strings();
+        let inner_muted = match (sounded.first(), sounded.last()) {
+            (Some(low), Some(high)) => {
+                all.iter().filter(|&e| e >= low && e <= high).count() - sounded.len()
+            }
+            _ => 0,
+        };
+        inner_muted as u32 * 3 + self.stretch() as u32
+    }
+
+    /// The chord's tones, then thinner and thinner versions of them for
+    /// instruments with fewer strings: doubled tones go first, then the
+    /// fifth, then the root, never the bass.
+    fn thinned(chord: &Chord) -> Vec<Vec<Tone>> {
+        let mut tones = chord.tones.clone();
+        let mut thinned = vec![tones.clone()];
+        let (core, _) = chord.chord_type.intervals();
+        let fifth = chord.tonic.add_interval(core[2].clone()).ok();
+        while tones.len() > 3 {
+            let doubled = (1..tones.len())
+                .rev()
+                .find(|&i| tones[..i].iter().any(|e| e.idx == tones[i].idx));
+            let fifth = tones
+                .iter()
+                .skip(1)
+                .position(|e| fifth.as_ref().is_some_and(|f| e.idx == f.idx))
+                .map(|i| i + 1);
+            let root = tones
+                .iter()
+                .skip(1)
+                .position(|e| e.idx == chord.tonic.idx)
+                .map(|i| i + 1);
+            match doubled.or(fifth).or(root) {
+                Some(i) => {
+                    tones.remove(i);
+                    thinned.push(tones.clone());
+                }
+                None => break,
+            }
+        }
+        thinned
+    }
+
+    /// Place the tones one by one on strings of rising open pitch and on
+    /// rising notes, keeping the fretted notes within the stretch.
+    fn place(
+        tones: &[Tone],
+        strings: &[u8],
+        order: &[usize],
+        placed: &mut Vec<(usize, u8)>,
+        found: &mut Vec<Vec<(usize, u8)>>,
+    ) {
+        let Some(tone) = tones.get(placed.len()) else {
+            found.push(placed.clone());
+            return;
+        };
+        let (first, floor) = match placed.last() {
+            Some((string, fret)) => (
+                order.iter().position(|e| e == string).unwrap_or(0) + 1,
+                strings[*string] + fret + 1,
+            ),
+            None => (0, 0),
+        };
+        for &string in order.iter().skip(first) {
+            for fret in 0..=FRETS {
+                let midi = strings[string] + fret;
+                if midi < floor || Pitch::class_of(midi) != tone.idx {
+                    continue;
+                }
+                let fretted = placed.iter().map(|e| e.1).chain([fret]).filter(|&e| e > 0);
+                let (low, high) = fretted.fold((u8::MAX, 0), |(l, h), e| (l.min(e), h.max(e)));
+                if high > 0 && high - low > MAX_STRETCH {
+                    continue;
+                }
+                placed.push((string, fret));
+                Self::place(tones, strings, order, placed, found);
+                placed.pop();
+            }
+        }
+    }
+
+    /// CAGED shape from the string holding the lowest root and whether the
+    /// root sits under the barre, at the lowest fretted note of the fingering,
+    /// or on the nut as an open string.
+    fn caged(placed: &[(usize, u8)], strings: &[u8], tonic: &Tone) -> Option<CagedShape> {
+        let (string, fret) = placed
+            .iter()
+            .find(|(string, fret)| Pitch::class_of(strings[*string] + fret) == tonic.idx)?;
+        let barre = placed.iter().map(|e| e.1).filter(|&f| f > 0).min();
+        // strings counted from the lowest, so the sixth string is 0
+        match (string, *fret == 0 || Some(*fret) == barre) {
+            (0, true) => Some(CagedShape::E),
+            (0, false) => Some(CagedShape::G),
+            (1, true) => Some(CagedShape::A),
+            (1, false) => Some(CagedShape::C),
+            (2, _) => Some(CagedShape::D),
+            _ => None,
+        }
+    }
+}
+
+/// The chord as the strings can play it: moved into the instrument's range,
+/// fingered as voiced if possible, otherwise in another inversion of its
+/// voicing type or in close position. Its tones are those fingered, so a
+/// chord thinned out for fewer strings is matched as played.
+pub(crate) fn fret(chord: &Chord, tuning: &Tuning) -> Option<Chord> {
+    let (low, high) = tuning.range();
+    let in_range = |e: &Chord| {
+        e.voicing.first().is_some_and(|p| p.midi >= low)
+            && e.voicing.last().is_some_and(|p| p.midi <= high)
+    };
+    let as_voiced = match in_range(chord) {
+        true => Ok(chord.clone()),
+        false => chord.revoiced(chord.layout.clone(), low),
+    };
+    let others = Voicing::all_vec(&chord.layout.voicing_type, &Difficulty::Guitar)
+        .into_iter()
+        .chain(Voicing::all_vec(&VoicingType::Close, &Difficulty::Guitar))
+        .map(|e| chord.revoiced(e, low));
+    std::iter::once(as_voiced).chain(others).find_map(|e| {
+        let mut e = e.ok()?;
+        let fingering = Fingering::of(&e, tuning)?;
+        e.tones = fingering.pitches.iter().map(|p| p.tone.clone()).collect();
+        e.voicing = fingering.pitches;
+        Some(e)
+    })
+}
+
+/// The chord fingered in the inversion of its voicing type that moves the
+/// least from the previous chord, so that voice leading holds on the strings.
+/// Falls back to `fret` when no inversion can be fingered.
+pub(crate) fn lead(previous: &Chord, chord: &Chord, tuning: &Tuning) -> Option<Chord> {
+    let (low, _) = tuning.range();
+    let mut best: Option<(u32, Chord)> = None;
+    for layout in Voicing::all_vec(&chord.layout.voicing_type, &Difficulty::Guitar).into_iter() {
+        let Ok(mut candidate) = chord.revoiced(layout, low) else {
+            continue;
+        };
+        let fingerings = Fingering::thinned(&candidate)
+            .into_iter()
+            .map(|tones| Fingering::all(&tones, &candidate.tonic, tuning))
+            .find(|e| !e.is_empty())
+            .unwrap_or_default();
+        for fingering in fingerings.into_iter() {
+            let cost =
+                voice_leading::motion(&previous.voicing, &fingering.pitches) + fingering.effort();
+            if best.as_ref().is_none_or(|(min, _)| cost < *min) {
+                candidate.tones = fingering.pitches.iter().map(|p| p.tone.clone()).collect();
+                candidate.voicing = fingering.pitches;
+                best = Some((cost, candidate.clone()));
+            }
+        }
+    }
+    match best {
+        Some((cost, led)) => {
+            debug!(
+                "fretboard::lead(): {:?} -> {:?}, {} semitones and effort",
+                previous.voicing, led.voicing, cost
+            );
+            Some(led)
+        }
+        None => fret(chord, tuning),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(placed: &[(usize, u8)], tonic: &str) -> Option<CagedShape> {
+        Fingering::caged(placed, &Tuning::Standard.strings(), &tonic.parse().unwrap())
+    }
+
+    #[test]
+    fn open_chords_take_their_own_shape() {
+        let e = [(0, 0), (1, 2), (2, 2), (3, 1), (4, 0), (5, 0)];
+        let c = [(1, 3), (2, 2), (3, 0), (4, 1), (5, 0)];
+        let g = [(0, 3), (1, 2), (2, 0), (3, 0), (4, 0), (5, 3)];
+        let d = [(2, 0), (3, 2), (4, 3), (5, 2)];
+        assert_eq!(shape(&e, "E"), Some(CagedShape::E));
+        assert_eq!(shape(&c, "C"), Some(CagedShape::C));
+        assert_eq!(shape(&g, "G"), Some(CagedShape::G));
+        assert_eq!(shape(&d, "D"), Some(CagedShape::D));
+    }
+
+    #[test]
+    fn barre_chords_are_read_from_the_lowest_fretted_note() {
+        let e_shape = [(0, 5), (1, 7), (2, 7), (3, 6), (4, 5), (5, 5)];
+        let a_shape = [(1, 5), (2, 7), (3, 7), (4, 7), (5, 5)];
+        assert_eq!(shape(&e_shape, "A"), Some(CagedShape::E));
+        assert_eq!(shape(&a_shape, "D"), Some(CagedShape::A));
+    }
+
+    #[test]
+    fn open_strings_do_not_lower_the_barre() {
+        let e_shape = [(0, 5), (1, 0), (2, 7), (3, 6), (4, 5), (5, 5)];
+        let a_shape = [(1, 5), (2, 0), (3, 7), (4, 7), (5, 5)];
+        assert_eq!(shape(&e_shape, "A"), Some(CagedShape::E));
+        assert_eq!(shape(&a_shape, "D"), Some(CagedShape::A));
+    }
+}
@@ -11,7 +11,9 @@ use std::fmt;
 
 use crate::app::Difficulty;
 use super::{
-    chord::{Chord, ChordType, Inversion},
+    chord::{Chord, ChordType, Voicing},
+    error::TheoryError,
+    scale::{Scale, ScaleType},
     tone::{Interval, NeutralTone, Tone, ToneVariant},
 };
 
@@ -24,6 +26,22 @@ pub enum KeyType {
     Mixolydian,
     Aeolian,
     Locrian,
+
+    HarmonicMinor,
+    LocrianNatural6,
+    IonianAugmented,
+    DorianSharp4,
+    PhrygianDominant,
+    LydianSharp2,
+    Ultralocrian,
+
+    MelodicMinor,
+    DorianFlat2,
+    LydianAugmented,
+    LydianDominant,
+    MixolydianFlat6,
+    LocrianNatural2,
+    Altered,
 }
 
 impl fmt::Display for KeyType {
@@ -50,17 +68,59 @@ impl fmt::Display for KeyType {
             KeyType::Locrian => {
                 write!(f, "{}", "7/Locrian".purple().bold().italic())
             }
+            KeyType::HarmonicMinor => {
+                write!(f, "{}", "hm1/HarmonicMinor".blue().bold().italic())
+            }
+            KeyType::LocrianNatural6 => {
+                write!(f, "{}", "hm2/LocrianNatural6".purple().bold().italic())
+            }
+            KeyType::IonianAugmented => {
+                write!(f, "{}", "hm3/IonianAugmented".green().bold().italic())
+            }
+            KeyType::DorianSharp4 => {
+                write!(f, "{}", "hm4/DorianSharp4".blue().bold().italic())
+            }
+            KeyType::PhrygianDominant => {
+                write!(f, "{}", "hm5/PhrygianDominant".yellow().bold().italic())
+            }
+            KeyType::LydianSharp2 => {
+                write!(f, "{}", "hm6/LydianSharp2".green().bold().italic())
+            }
+            KeyType::Ultralocrian => {
+                write!(f, "{}", "hm7/Ultralocrian".red().bold().italic())
+            }
+            KeyType::MelodicMinor => {
+                write!(f, "{}", "mm1/MelodicMinor".blue().bold().italic())
+            }
+            KeyType::DorianFlat2 => {
+                write!(f, "{}", "mm2/DorianFlat2".cyan().bold().italic())
+            }
+            KeyType::LydianAugmented => {
+                write!(f, "{}", "mm3/LydianAugmented".green().bold().italic())
+            }
+            KeyType::LydianDominant => {
+                write!(f, "{}", "mm4/LydianDominant".yellow().bold().italic())
+            }
+            KeyType::MixolydianFlat6 => {
+                write!(f, "{}", "mm5/MixolydianFlat6".yellow().bold().italic())
+            }
+            KeyType::LocrianNatural2 => {
+                write!(f, "{}", "mm6/LocrianNatural2".purple().bold().italic())
+            }
+            KeyType::Altered => {
+                write!(f, "{}", "mm7/Altered".red().bold().italic())
+            }
         }
     }
 }
 
 impl KeyType {
-    pub fn sample(difficulty: Difficulty) -> anyhow::Result<Self> {
+    pub fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
         // let mut rng_seed = ChaCha8Rng::seed_from_u64(42);
         let mut rng_seed = rand::thread_rng();
         let prob = match difficulty {
-            Difficulty::Piano => [1.0; 7],
-            Difficulty::Guitar => [1.0; 7],
+            Difficulty::Piano => [1.0; 21],
+            Difficulty::Guitar => [1.0; 21],
         };
 
         let mnm = Categorical::new(&prob)?;
@@ -72,9 +132,328 @@ impl KeyType {
             4 => KeyType::Mixolydian,
             5 => KeyType::Aeolian,
             6 => KeyType::Locrian,
-            _ => panic!("random error"),
+            7 => KeyType::HarmonicMinor,
+            8 => KeyType::LocrianNatural6,
+            9 => KeyType::IonianAugmented,
+            10 => KeyType::DorianSharp4,
+            11 => KeyType::PhrygianDominant,
+            12 => KeyType::LydianSharp2,
+            13 => KeyType::Ultralocrian,
+            14 => KeyType::MelodicMinor,
+            15 => KeyType::DorianFlat2,
+            16 => KeyType::LydianAugmented,
+            17 => KeyType::LydianDominant,
+            18 => KeyType::MixolydianFlat6,
+            19 => KeyType::LocrianNatural2,
+            20 => KeyType::Altered,
+            idx => {
+                return Err(TheoryError::Sample {
+                    what: "key type",
+                    idx,
+                })
+            }
         })
     }
+
+    /// Spelled intervals of the seven degrees above the tonic.
+    pub(crate) fn interval_vec(&self) -> Vec<Interval> {
+        use Interval::*;
+        match self {
+            KeyType::Ionian => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MajorSixth,
+                MajorSeventh,
+            ],
+            KeyType::Dorian => vec![
+                PerfectUnison,
+                MajorSecond,
+                MinorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MajorSixth,
+                MinorSeventh,
+            ],
+            KeyType::Phrygian => vec![
+                PerfectUnison,
+                MinorSecond,
+                MinorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MinorSixth,
+                MinorSeventh,
+            ],
+            KeyType::Lydian => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                AugmentedFourth,
+                PerfectFifth,
+                MajorSixth,
+                MajorSeventh,
+            ],
+            KeyType::Mixolydian => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MajorSixth,
+                MinorSeventh,
+            ],
+            KeyType::Aeolian => vec![
+                PerfectUnison,
+                MajorSecond,
+                MinorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MinorSixth,
+                MinorSeventh,
+            ],
+            KeyType::Locrian => vec![
+                PerfectUnison,
+                MinorSecond,
+                MinorThird,
+                PerfectFourth,
+                DiminishedFifth,
+                MinorSixth,
+                MinorSeventh,
+            ],
+            KeyType::HarmonicMinor => vec![
+                PerfectUnison,
+                MajorSecond,
+                MinorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MinorSixth,
+                MajorSeventh,
+            ],
+            KeyType::LocrianNatural6 => vec![
+                PerfectUnison,
+                MinorSecond,
+                MinorThird,
+                PerfectFourth,
+                DiminishedFifth,
+                MajorSixth,
+                MinorSeventh,
+            ],
+            KeyType::IonianAugmented => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                PerfectFourth,
+                AugmentedFifth,
+                MajorSixth,
+                MajorSeventh,
+            ],
+            KeyType::DorianSharp4 => vec![
+                PerfectUnison,
+                MajorSecond,
+                MinorThird,
+                AugmentedFourth,
+                PerfectFifth,
+                MajorSixth,
+                MinorSeventh,
+            ],
+            KeyType::PhrygianDominant => vec![
+                PerfectUnison,
+                MinorSecond,
+                MajorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MinorSixth,
+                MinorSeventh,
+            ],
+            KeyType::LydianSharp2 => vec![
+                PerfectUnison,
+                AugmentedSecond,
+                MajorThird,
+                AugmentedFourth,
+                PerfectFifth,
+                MajorSixth,
+                MajorSeventh,
+            ],
+            KeyType::Ultralocrian => vec![
+                PerfectUnison,
+                MinorSecond,
+                MinorThird,
+                DiminishedFourth,
+                DiminishedFifth,
+                MinorSixth,
+                DiminishedSeventh,
+            ],
+            KeyType::MelodicMinor => vec![
+                PerfectUnison,
+                MajorSecond,
+                MinorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MajorSixth,
+                MajorSeventh,
+            ],
+            KeyType::DorianFlat2 => vec![
+                PerfectUnison,
+                MinorSecond,
+                MinorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MajorSixth,
+                MinorSeventh,
+            ],
+            KeyType::LydianAugmented => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                AugmentedFourth,
+                AugmentedFifth,
+                MajorSixth,
+                MajorSeventh,
+            ],
+            KeyType::LydianDominant => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                AugmentedFourth,
+                PerfectFifth,
+                MajorSixth,
+                MinorSeventh,
+            ],
+            KeyType::MixolydianFlat6 => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MinorSixth,
+                MinorSeventh,
+            ],
+            KeyType::LocrianNatural2 => vec![
+                PerfectUnison,
+                MajorSecond,
+                MinorThird,
+                PerfectFourth,
+                DiminishedFifth,
+                MinorSixth,
+                MinorSeventh,
+            ],
+            KeyType::Altered => vec![
+                PerfectUnison,
+                MinorSecond,
+                MinorThird,
+                DiminishedFourth,
+                DiminishedFifth,
+                MinorSixth,
+                MinorSeventh,
+            ],
+        }
+    }
+
+    /// The seven modes of the parent scale, starting from its first mode.
+    pub(crate) fn family_vec(&self) -> Vec<KeyType> {
+        match self {
+            KeyType::Ionian
+            | KeyType::Dorian
+            | KeyType::Phrygian
+            | KeyType::Lydian
+            | KeyType::Mixolydian
+            | KeyType::Aeolian
+            | KeyType::Locrian => {
+                vec![
+                    KeyType::Ionian,
+                    KeyType::Dorian,
+                    KeyType::Phrygian,
+                    KeyType::Lydian,
+                    KeyType::Mixolydian,
+                    KeyType::Aeolian,
+                    KeyType::Locrian,
+                ]
+            }
+            KeyType::HarmonicMinor
+            | KeyType::LocrianNatural6
+            | KeyType::IonianAugmented
+            | KeyType::DorianSharp4
+            | KeyType::PhrygianDominant
+            | KeyType::LydianSharp2
+            | KeyType::Ultralocrian => {
+                vec![
+                    KeyType::HarmonicMinor,
+                    KeyType::LocrianNatural6,
+                    KeyType::IonianAugmented,
+                    KeyType::DorianSharp4,
+                    KeyType::PhrygianDominant,
+                    KeyType::LydianSharp2,
+                    KeyType::Ultralocrian,
+                ]
+            }
+            KeyType::MelodicMinor
+            | KeyType::DorianFlat2
+            | KeyType::LydianAugmented
+            | KeyType::LydianDominant
+            | KeyType::MixolydianFlat6
+            | KeyType::LocrianNatural2
+            | KeyType::Altered => {
+                vec![
+                    KeyType::MelodicMinor,
+                    KeyType::DorianFlat2,
+                    KeyType::LydianAugmented,
+                    KeyType::LydianDominant,
+                    KeyType::MixolydianFlat6,
+                    KeyType::LocrianNatural2,
+                    KeyType::Altered,
+                ]
+            }
+        }
+    }
+
+    /// Position of the mode within its parent scale, 0-based.
+    pub(crate) fn family_idx(&self) -> usize {
+        self.family_vec()
+            .iter()
+            .position(|e| e == self)
+            .unwrap_or(0)
+    }
+
+    /// Tonics of the parent scale. Major keys stop at six accidentals, minor
+    /// keys go up to G# and D#, whose leading tones are double sharps.
+    pub(crate) fn parent_tonic_vec(&self) -> Vec<Tone> {
+        match self.family_vec()[0] {
+            KeyType::Ionian => Vec::from([
+                Tone::new(NeutralTone::C, ToneVariant::Neutral),
+                Tone::new(NeutralTone::D, ToneVariant::Flat),
+                Tone::new(NeutralTone::D, ToneVariant::Neutral),
+                Tone::new(NeutralTone::E, ToneVariant::Flat),
+                Tone::new(NeutralTone::E, ToneVariant::Neutral),
+                Tone::new(NeutralTone::F, ToneVariant::Neutral),
+                Tone::new(NeutralTone::F, ToneVariant::Sharp),
+                Tone::new(NeutralTone::G, ToneVariant::Flat),
+                Tone::new(NeutralTone::G, ToneVariant::Neutral),
+                Tone::new(NeutralTone::A, ToneVariant::Flat),
+                Tone::new(NeutralTone::A, ToneVariant::Neutral),
+                Tone::new(NeutralTone::B, ToneVariant::Flat),
+                Tone::new(NeutralTone::B, ToneVariant::Neutral),
+            ]),
+            // the flat spelling comes first, it is the one rematched to
+            _ => Vec::from([
+                Tone::new(NeutralTone::C, ToneVariant::Neutral),
+                Tone::new(NeutralTone::C, ToneVariant::Sharp),
+                Tone::new(NeutralTone::D, ToneVariant::Neutral),
+                Tone::new(NeutralTone::E, ToneVariant::Flat),
+                Tone::new(NeutralTone::D, ToneVariant::Sharp),
+                Tone::new(NeutralTone::E, ToneVariant::Neutral),
+                Tone::new(NeutralTone::F, ToneVariant::Neutral),
+                Tone::new(NeutralTone::F, ToneVariant::Sharp),
+                Tone::new(NeutralTone::G, ToneVariant::Neutral),
+                Tone::new(NeutralTone::A, ToneVariant::Flat),
+                Tone::new(NeutralTone::G, ToneVariant::Sharp),
+                Tone::new(NeutralTone::A, ToneVariant::Neutral),
+                Tone::new(NeutralTone::B, ToneVariant::Flat),
+                Tone::new(NeutralTone::B, ToneVariant::Neutral),
+            ]),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -97,7 +476,7 @@ impl Key {
         }
     }
 
-    pub(crate) fn sample(difficulty: Difficulty) -> anyhow::Result<Self> {
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
         let mut rng_seed = rand::thread_rng(); // no seed
         let prob_tonic = [1.0; 13];
         let mnm_tonic = Categorical::new(&prob_tonic)?;
@@ -118,11 +497,31 @@ impl Key {
             10 => Tone::new(NeutralTone::F, ToneVariant::Neutral), // 1b
             11 => Tone::new(NeutralTone::E, ToneVariant::Flat),    // 3b
             12 => Tone::new(NeutralTone::D, ToneVariant::Flat),    // 5b
-            _ => panic!("random error"),
+            idx => return Err(TheoryError::Sample { what: "tonic", idx }),
         };
-        let ionian = Key {
-            tonic,
-            key_type: KeyType::Ionian,
+        let prob_family = match difficulty {
+            Difficulty::Piano => [1.0; 3],
+            Difficulty::Guitar => [1.0; 3],
+        };
+        let mnm_family = Categorical::new(&prob_family)?;
+        let key_type = match mnm_family.sample(&mut rng_seed) as i32 {
+            0 => KeyType::Ionian,
+            1 => KeyType::HarmonicMinor,
+            2 => KeyType::MelodicMinor,
+            idx => {
+                return Err(TheoryError::Sample {
+                    what: "mode family",
+                    idx,
+                })
+            }
+        };
+        let parent = match key_type {
+            KeyType::Ionian => Key { tonic, key_type },
+            _ => {
+                let minor_tonics = key_type.parent_tonic_vec();
+                let tonic = minor_tonics[rng_seed.gen_range(0..minor_tonics.len())].clone();
+                Key { tonic, key_type }
+            }
         };
         let prob_mode = match difficulty {
             Difficulty::Piano => [1.0; 7],
@@ -131,223 +530,208 @@ impl Key {
 
         let mnm_mode = Categorical::new(&prob_mode)?;
         let mode_idx = mnm_mode.sample(&mut rng_seed) as i8;
-        let new_key = ionian.change_mode(mode_idx);
-        Ok(new_key)
+        parent.change_mode(mode_idx)
     }
 
-    pub(crate) fn log_all_chords(&self, difficulty: Difficulty) -> anyhow::Result<()> {
+    pub(crate) fn log_all_chords(&self, difficulty: Difficulty) -> Result<(), TheoryError> {
         for i in 1..=7 {
             info!("{}", self.gen_chord(i, difficulty.clone())?);
         }
         Ok(())
     }
 
-    fn gen_tone(&self, idx: i8) -> Tone {
-        let interval = match idx {
-            1 => Interval::PerfectUnison,
-            2 => match self.key_type {
-                KeyType::Ionian
-                | KeyType::Lydian
-                | KeyType::Mixolydian
-                | KeyType::Aeolian
-                | KeyType::Dorian => Interval::MajorSecond,
-                KeyType::Phrygian | KeyType::Locrian => Interval::MinorSecond,
-            },
-            3 => match self.key_type {
-                KeyType::Ionian | KeyType::Lydian | KeyType::Mixolydian => Interval::MajorThird,
-                KeyType::Aeolian | KeyType::Dorian | KeyType::Locrian | KeyType::Phrygian => {
-                    Interval::MinorThird
-                }
-            },
-            4 => match self.key_type {
-                KeyType::Ionian
-                | KeyType::Phrygian
-                | KeyType::Mixolydian
-                | KeyType::Aeolian
-                | KeyType::Dorian
-                | KeyType::Locrian => Interval::PerfectFourth,
-                KeyType::Lydian => Interval::AugmentedFourth,
-            },
-            5 => match self.key_type {
-                KeyType::Ionian
-                | KeyType::Phrygian
-                | KeyType::Lydian
-                | KeyType::Mixolydian
-                | KeyType::Aeolian
-                | KeyType::Dorian => Interval::PerfectFifth,
-                KeyType::Locrian => Interval::DiminishedFifth,
-            },
-            6 => match self.key_type {
-                KeyType::Ionian | KeyType::Lydian | KeyType::Mixolydian | KeyType::Dorian => {
-                    Interval::MajorSixth
-                }
-                KeyType::Phrygian | KeyType::Aeolian | KeyType::Locrian => Interval::MinorSixth,
-            },
-            7 => match self.key_type {
-                KeyType::Ionian | KeyType::Lydian => Interval::MajorSeventh,
-                KeyType::Phrygian
-                | KeyType::Mixolydian
-                | KeyType::Aeolian
-                | KeyType::Locrian
-                | KeyType::Dorian => Interval::MinorSeventh,
-            },
-            _ => panic!("idx for getting tone not valid"),
-        };
+    pub(crate) fn gen_tone(&self, idx: i8) -> Result<Tone, TheoryError> {
+        if !(1..=7).contains(&idx) {
+            return Err(TheoryError::InvalidDegree(idx));
+        }
+        self.degree_tone(idx)
+    }
+
+    /// Tone of a degree counted around the scale, so 8 is the tonic again.
+    pub(crate) fn degree_tone(&self, idx: i8) -> Result<Tone, TheoryError> {
+        let interval = self.key_type.interval_vec()[(idx - 1).rem_euclid(7) as usize].clone();
         let matched_tonic = self.tonic.clone().rematch_key(&self.key_type);
         matched_tonic.add_interval(interval)
     }
 
-    pub(crate) fn gen_chord(&self, idx: i8, difficulty: Difficulty) -> anyhow::Result<Chord> {
-        info!("Key::gen_chord(): generate {}th chord for {}", idx, self);
-        let chord_type = match self.key_type {
-            KeyType::Ionian => match idx {
-                1 | 4 => ChordType::Major7,
-                2 | 3 | 6 => ChordType::Minor7,
-                5 => ChordType::Dominant7,
-                7 => ChordType::HalfDiminished7,
-                _ => panic!("chord idx not valid"),
-            },
-            KeyType::Dorian => match idx {
-                7 | 3 => ChordType::Major7,
-                1 | 2 | 5 => ChordType::Minor7,
-                4 => ChordType::Dominant7,
-                6 => ChordType::HalfDiminished7,
-                _ => panic!("chord idx not valid"),
-            },
-            KeyType::Phrygian => match idx {
-                6 | 2 => ChordType::Major7,
-                7 | 1 | 4 => ChordType::Minor7,
-                3 => ChordType::Dominant7,
-                5 => ChordType::HalfDiminished7,
-                _ => panic!("chord idx not valid"),
-            },
-            KeyType::Lydian => match idx {
-                5 | 1 => ChordType::Major7,
-                6 | 7 | 3 => ChordType::Minor7,
-                2 => ChordType::Dominant7,
-                4 => ChordType::HalfDiminished7,
-                _ => panic!("chord idx not valid"),
-            },
-            KeyType::Mixolydian => match idx {
-                4 | 7 => ChordType::Major7,
-                5 | 6 | 2 => ChordType::Minor7,
-                1 => ChordType::Dominant7,
-                3 => ChordType::HalfDiminished7,
-                _ => panic!("chord idx not valid"),
-            },
-            KeyType::Aeolian => match idx {
-                3 | 6 => ChordType::Major7,
-                1 | 4 | 5 => ChordType::Minor7,
-                7 => ChordType::Dominant7,
-                2 => ChordType::HalfDiminished7,
-                _ => panic!("chord idx not valid"),
-            },
-            KeyType::Locrian => match idx {
-                2 | 5 => ChordType::Major7,
-                7 | 3 | 4 => ChordType::Minor7,
-                6 => ChordType::Dominant7,
-                1 => ChordType::HalfDiminished7,
-                _ => panic!("chord idx not valid"),
-            },
+    /// Pitch class (1-12) of a degree counted around the scale, for when the
+    /// spelling does not matter.
+    pub(crate) fn degree_idx(&self, idx: i8) -> i8 {
+        let interval = self.key_type.interval_vec()[(idx - 1).rem_euclid(7) as usize].clone();
+        (self.tonic.idx - 1 + interval.key_diff()).rem_euclid(12) + 1
+    }
+
+    /// The seven degrees of the mode, spelled from the tonic up.
+    pub(crate) fn scale(&self) -> Result<Scale, TheoryError> {
+        Ok(Scale {
+            tonic: self.degree_tone(1)?,
+            scale_type: ScaleType::Mode(self.key_type.clone()),
+            tones: (1..=7)
+                .map(|i| self.degree_tone(i))
+                .collect::<Result<Vec<Tone>, TheoryError>>()?,
+        })
+    }
+
+    /// Accidentals of the key signature in the order they are written, the
+    /// sharps up by fifths from F or the flats down by fifths from B. Modes of
+    /// the major scale share the signature of their parent. Modes of harmonic
+    /// and melodic minor take the one of the natural minor on their parent
+    /// tonic, and their raised degrees are written as accidentals.
+    pub(crate) fn signature(&self) -> Result<Vec<Tone>, TheoryError> {
+        let mode = self.key_type.family_idx() as i8;
+        let scale = match self.key_type.family_vec()[0] {
+            KeyType::Ionian => self.scale()?,
+            _ => Key {
+                tonic: self.degree_tone((7 - mode) % 7 + 1)?,
+                key_type: KeyType::Aeolian,
+            }
+            .scale()?,
         };
-        Ok(Chord::new(
-            self.gen_tone(idx),
+        let mut accidentals: Vec<Tone> =
+            scale.tones.into_iter().filter(|e| e.alter() != 0).collect();
+        // position in F C G D A E B, each letter a fifth above the one before
+        accidentals.sort_by_key(|e| {
+            let fifths = (e.letter_step() * 2 + 1) % 7;
+            match e.alter() > 0 {
+                true => fifths,
+                false => 6 - fifths,
+            }
+        });
+        Ok(accidentals)
+    }
+
+    /// Quality of the diatonic seventh chord stacked in thirds on a degree.
+    pub(crate) fn gen_chord_type(&self, idx: i8) -> Result<ChordType, TheoryError> {
+        let root = self.gen_tone(idx)?.idx;
+        let diff = |step: i8| (self.degree_idx(idx + step) - root + 12) % 12;
+        Ok(match (diff(2), diff(4), diff(6)) {
+            (4, 7, 11) => ChordType::Major7,
+            (3, 7, 10) => ChordType::Minor7,
+            (4, 7, 10) => ChordType::Dominant7,
+            (3, 6, 10) => ChordType::HalfDiminished7,
+            (3, 6, 9) => ChordType::Diminished7,
+            (3, 7, 11) => ChordType::MinorMajor7,
+            (4, 8, 10) => ChordType::Augmented7,
+            (4, 8, 11) => ChordType::AugmentedMajor7,
+            _ => {
+                return Err(TheoryError::UnknownQuality {
+                    key: self.to_string(),
+                    degree: idx,
+                })
+            }
+        })
+    }
+
+    pub(crate) fn gen_chord(&self, idx: i8, difficulty: Difficulty) -> Result<Chord, TheoryError> {
+        info!("Key::gen_chord(): generate {}th chord for {}", idx, self);
+        let chord_type = self.gen_chord_type(idx)?;
+        Chord::new(
+            self.gen_tone(idx)?,
             chord_type,
-            Inversion::sample(difficulty)?,
-        ))
+            Voicing::sample(difficulty)?,
+        )
     }
 
-    pub(crate) fn change_mode(&self, idx: i8) -> Key {
+    /// Chord types on the given degree whose tones all stay inside the mode,
+    /// i.e. the diatonic seventh chord plus every diatonic extension of it.
+    pub(crate) fn diatonic_chord_types(&self, idx: i8) -> Result<Vec<ChordType>, TheoryError> {
+        let scale: Vec<i8> = (1..=7).map(|i| self.degree_idx(i)).collect();
+        let root = self.gen_tone(idx)?;
+        Ok(ChordType::all_vec()
+            .into_iter()
+            .filter(|chord_type| {
+                chord_type
+                    .interval_vec()
+                    .iter()
+                    .all(|e| scale.contains(&((root.idx - 1 + e.key_diff()) % 12 + 1)))
+            })
+            .collect())
+    }
+
+    /// A chord on the degree in any of its diatonic qualities, e.g. Imaj7, I6
+    /// or Imaj9, so that the drilled targets are not only seventh chords.
+    pub(crate) fn gen_extended_chord(
+        &self,
+        idx: i8,
+        difficulty: Difficulty,
+    ) -> Result<Chord, TheoryError> {
+        let chord_type = self
+            .diatonic_chord_types(idx)?
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .ok_or(TheoryError::NoCandidate("diatonic chord type"))?;
+        info!(
+            "Key::gen_extended_chord(): generate {}th chord {} for {}",
+            idx, chord_type, self
+        );
+        Chord::new(
+            self.gen_tone(idx)?,
+            chord_type,
+            Voicing::sample(difficulty)?,
+        )
+    }
+
+    /// A chord borrowed from a parallel mode: bVI, bVII or iv from aeolian, or
+    /// bII from phrygian. Chords that are already in this key are skipped when
+    /// there is a choice.
+    pub(crate) fn gen_borrowed_chord(&self, difficulty: Difficulty) -> Result<Chord, TheoryError> {
+        let scale: Vec<i8> = (1..=7).map(|i| self.degree_idx(i)).collect();
+        let aeolian = Key::new(self.tonic.clone(), KeyType::Aeolian);
+        let phrygian = Key::new(self.tonic.clone(), KeyType::Phrygian);
+        let candidates = Vec::from([
+            aeolian.gen_chord(6, difficulty.clone())?,
+            aeolian.gen_chord(7, difficulty.clone())?,
+            aeolian.gen_chord(4, difficulty.clone())?,
+            phrygian.gen_chord(2, difficulty)?,
+        ]);
+        let borrowed: Vec<&Chord> = candidates
+            .iter()
+            .filter(|chord| !chord.tones.iter().all(|e| scale.contains(&e.idx)))
+            .collect();
+        let chord = match borrowed.is_empty() {
+            true => &candidates[rand::thread_rng().gen_range(0..candidates.len())],
+            false => borrowed[rand::thread_rng().gen_range(0..borrowed.len())],
+        };
+        debug!("Key::gen_borrowed_chord(): {} borrowed in {}", chord, self);
+        Ok(chord.clone())
+    }
+
+    /// Keys of the same mode a major or minor third above or below, whose
+    /// tonic chords share a single tone with this one (C: E, Eb, A, Ab).
+    pub(crate) fn gen_chromatic_mediants(&self) -> Vec<Key> {
+        // a third below is spelled as a sixth above
+        [
+            Interval::MajorThird,
+            Interval::MinorThird,
+            Interval::MajorSixth,
+            Interval::MinorSixth,
+        ]
+        .into_iter()
+        .filter_map(|interval| {
+            let tonic = self.tonic.add_interval(interval).ok()?;
+            Some(Key::new(tonic, self.key_type.clone()))
+        })
+        .collect()
+    }
+
+    pub(crate) fn change_mode(&self, idx: i8) -> Result<Key, TheoryError> {
+        if !(0..7).contains(&idx) {
+            return Err(TheoryError::InvalidMode(idx));
+        }
         let key_type = self.derived_keytype_vec()[idx as usize].clone();
-        let tonic = self.gen_tone(idx + 1);
+        let tonic = self.degree_tone(idx + 1)?;
         let new_key = Key { tonic, key_type };
 
         debug!(
             "Key::change_mode(): change {} to {} using idx {}",
             self, new_key, idx
         );
-        new_key
+        Ok(new_key)
     }
 
     fn derived_keytype_vec(&self) -> Vec<KeyType> {
-        match self.key_type {
-            KeyType::Ionian => {
-                vec![
-                    KeyType::Ionian,
-                    KeyType::Dorian,
-                    KeyType::Phrygian,
-                    KeyType::Lydian,
-                    KeyType::Mixolydian,
-                    KeyType::Aeolian,
-                    KeyType::Locrian,
-                ]
-            }
-            KeyType::Dorian => {
-                vec![
-                    KeyType::Dorian,
-                    KeyType::Phrygian,
-                    KeyType::Lydian,
-                    KeyType::Mixolydian,
-                    KeyType::Aeolian,
-                    KeyType::Locrian,
-                    KeyType::Ionian,
-                ]
-            }
-            KeyType::Phrygian => {
-                vec![
-                    KeyType::Phrygian,
-                    KeyType::Lydian,
-                    KeyType::Mixolydian,
-                    KeyType::Aeolian,
-                    KeyType::Locrian,
-                    KeyType::Ionian,
-                    KeyType::Dorian,
-                ]
-            }
-            KeyType::Lydian => {
-                vec![
-                    KeyType::Lydian,
-                    KeyType::Mixolydian,
-                    KeyType::Aeolian,
-                    KeyType::Locrian,
-                    KeyType::Ionian,
-                    KeyType::Dorian,
-                    KeyType::Phrygian,
-                ]
-            }
-            KeyType::Mixolydian => {
-                vec![
-                    KeyType::Mixolydian,
-                    KeyType::Aeolian,
-                    KeyType::Locrian,
-                    KeyType::Ionian,
-                    KeyType::Dorian,
-                    KeyType::Phrygian,
-                    KeyType::Lydian,
-                ]
-            }
-            KeyType::Aeolian => {
-                vec![
-                    KeyType::Aeolian,
-                    KeyType::Locrian,
-                    KeyType::Ionian,
-                    KeyType::Dorian,
-                    KeyType::Phrygian,
-                    KeyType::Lydian,
-                    KeyType::Mixolydian,
-                ]
-            }
-            KeyType::Locrian => {
-                vec![
-                    KeyType::Locrian,
-                    KeyType::Ionian,
-                    KeyType::Dorian,
-                    KeyType::Phrygian,
-                    KeyType::Lydian,
-                    KeyType::Mixolydian,
-                    KeyType::Aeolian,
-                ]
-            }
-        }
+        let mut key_types = self.key_type.family_vec();
+        key_types.rotate_left(self.key_type.family_idx());
+        key_types
     }
 }
@@ -1,4 +1,16 @@
+pub(crate) mod analysis;
+pub(crate) mod cadence;
 pub(crate) mod chord;
+pub(crate) mod dyad;
+pub(crate) mod error;
+pub(crate) mod fretboard;
 pub(crate) mod key;
 pub(crate) mod modulation;
+pub(crate) mod neo_riemannian;
+pub(crate) mod parse;
+pub(crate) mod pitch;
+pub(crate) mod scale;
+pub(crate) mod sequence;
+pub(crate) mod template;
 pub(crate) mod tone;
+pub(crate) mod voice_leading;
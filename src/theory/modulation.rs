@@ -10,7 +10,18 @@ use statrs::distribution::Categorical;
 use std::fmt;
 
 use crate::app::Difficulty;
-use super::chord::Chord;
+use super::{
+    chord::{Chord, ChordType},
+    error::TheoryError,
+};
+
+/// Extended dominant chain: how many dominants walk back from the target
+/// around the cycle of fifths, and whether any of them may be a tritone sub.
+#[derive(Debug, Clone)]
+pub(crate) struct Chain {
+    pub(crate) depth: usize,
+    pub(crate) tritone_subs: bool,
+}
 
 pub(crate) enum DeTour {
     Straight,
@@ -18,6 +29,11 @@ pub(crate) enum DeTour {
     SubstituteSD,
     SD25,
     SSD25,
+    Backdoor,
+    MinorPlagal,
+    TritoneSub25,
+    SubV25,
+    ExtendedDominants(Chain),
 }
 
 impl fmt::Display for DeTour {
@@ -38,18 +54,37 @@ impl fmt::Display for DeTour {
             DeTour::SSD25 => {
                 write!(f, "{}", "SSD25".purple().bold())
             }
+            DeTour::Backdoor => {
+                write!(f, "{}", "Backdoor".yellow().bold())
+            }
+            DeTour::MinorPlagal => {
+                write!(f, "{}", "MinorPlagal".cyan().bold())
+            }
+            DeTour::TritoneSub25 => {
+                write!(f, "{}", "TritoneSub25".bright_blue().bold())
+            }
+            DeTour::SubV25 => {
+                write!(f, "{}", "SubV25".bright_purple().bold())
+            }
+            DeTour::ExtendedDominants(chain) => {
+                let name = format!("ExtendedDominants({})", chain.depth);
+                write!(f, "{}", name.bright_green().bold())
+            }
         }
     }
 }
 
 impl DeTour {
-    pub(crate) fn sample(difficulty: Difficulty) -> anyhow::Result<Self> {
+    pub(crate) fn sample(difficulty: Difficulty, chain: &Chain) -> Result<Self, TheoryError> {
         // let mut rng_seed = ChaCha8Rng::seed_from_u64(42);
         let mut rng_seed = rand::thread_rng();
-        let prob = match difficulty {
-            Difficulty::Piano => [1.0; 5],
-            Difficulty::Guitar => [1.0; 5],
+        let mut prob = match difficulty {
+            Difficulty::Piano => [1.0; 10],
+            Difficulty::Guitar => [1.0; 10],
         };
+        if chain.depth == 0 {
+            prob[9] = 0.0;
+        }
 
         let mnm = Categorical::new(&prob)?;
         let detour = match mnm.sample(&mut rng_seed) as i32 {
@@ -58,7 +93,17 @@ impl DeTour {
             2 => DeTour::SubstituteSD,
             3 => DeTour::SD25,
             4 => DeTour::SSD25,
-            _ => panic!("random error"),
+            5 => DeTour::Backdoor,
+            6 => DeTour::MinorPlagal,
+            7 => DeTour::TritoneSub25,
+            8 => DeTour::SubV25,
+            9 => DeTour::ExtendedDominants(chain.clone()),
+            idx => {
+                return Err(TheoryError::Sample {
+                    what: "detour",
+                    idx,
+                })
+            }
         };
         info!("DeTour::sample(): {} sampled", detour);
         Ok(detour)
@@ -68,7 +113,7 @@ impl DeTour {
         &self,
         chord: Chord,
         difficulty: Difficulty,
-    ) -> anyhow::Result<Vec<Chord>> {
+    ) -> Result<Vec<Chord>, TheoryError> {
         match self {
             DeTour::Straight => Ok(Vec::from([chord])),
             DeTour::SecondaryDominant => {
@@ -89,6 +134,45 @@ impl DeTour {
                 let pp_chord = pre_chord.gen_second_minor(difficulty.clone())?;
                 Ok(Vec::from([chord, pre_chord, pp_chord]))
             }
+            DeTour::Backdoor => {
+                // iv7 - bVII7 - I
+                let pre_chord = chord.gen_backdoor_dominant(difficulty.clone())?;
+                let pp_chord =
+                    chord.gen_minor_subdominant(ChordType::Minor7, difficulty.clone())?;
+                Ok(Vec::from([chord, pre_chord, pp_chord]))
+            }
+            DeTour::MinorPlagal => {
+                // ivm6 - I
+                let pre_chord = chord.gen_minor_subdominant(ChordType::Minor6, difficulty)?;
+                Ok(Vec::from([chord, pre_chord]))
+            }
+            DeTour::TritoneSub25 => {
+                // bvi7 - bII7 - I, the related ii of the tritone sub
+                let pre_chord = chord.gen_tritone_sub(difficulty.clone())?;
+                let pp_chord = pre_chord.gen_second_minor(difficulty.clone())?;
+                Ok(Vec::from([chord, pre_chord, pp_chord]))
+            }
+            DeTour::SubV25 => {
+                // ii7 - bII7 - I
+                let pre_chord = chord.gen_tritone_sub(difficulty.clone())?;
+                let pp_chord = chord.gen_supertonic_minor(difficulty.clone())?;
+                Ok(Vec::from([chord, pre_chord, pp_chord]))
+            }
+            DeTour::ExtendedDominants(chain) => {
+                // ... V7/V/V - V7/V - V7 - I, each step may be swapped for its tritone sub
+                let mut rng = rand::thread_rng();
+                let mut chords = Vec::from([chord]);
+                for _ in 0..chain.depth {
+                    let pre = &chords[chords.len() - 1];
+                    let pre_chord = if chain.tritone_subs && rng.gen_bool(0.5) {
+                        pre.gen_tritone_sub(difficulty.clone())?
+                    } else {
+                        pre.gen_secondary_dominant(difficulty.clone())?
+                    };
+                    chords.push(pre_chord);
+                }
+                Ok(chords)
+            }
         }
     }
 }
@@ -100,6 +184,9 @@ pub enum Modulation {
     ViaSharedChord,
     ViaDiminished,
     Back,
+    Borrowed,
+    ChromaticMediant,
+    CommonTone,
 }
 
 impl fmt::Display for Modulation {
@@ -132,17 +219,40 @@ impl fmt::Display for Modulation {
             Modulation::Back => {
                 write!(f, "{}", "(back to the previous key)".yellow().bold())
             }
+            Modulation::Borrowed => {
+                write!(
+                    f,
+                    "{}",
+                    "(borrow a chord from a parallel mode)".purple().bold()
+                )
+            }
+            Modulation::ChromaticMediant => {
+                write!(
+                    f,
+                    "{}",
+                    "(to a key a third away sharing one tone)".red().bold()
+                )
+            }
+            Modulation::CommonTone => {
+                write!(
+                    f,
+                    "{}",
+                    "(hold a common tone into the new key's dominant)"
+                        .bright_blue()
+                        .bold()
+                )
+            }
         }
     }
 }
 
 impl Modulation {
-    pub(crate) fn sample(difficulty: Difficulty) -> anyhow::Result<Self> {
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
         let mut rng_seed = rand::thread_rng();
 
         let prob = match difficulty {
-            Difficulty::Piano => [1.0; 5],
-            Difficulty::Guitar => [1.0; 5],
+            Difficulty::Piano => [1.0; 8],
+            Difficulty::Guitar => [1.0; 8],
         };
 
         let mnm = Categorical::new(&prob)?;
@@ -152,7 +262,15 @@ impl Modulation {
             2 => Modulation::ViaSharedChord,
             3 => Modulation::ViaDiminished,
             4 => Modulation::Back,
-            _ => panic!("random error"),
+            5 => Modulation::Borrowed,
+            6 => Modulation::ChromaticMediant,
+            7 => Modulation::CommonTone,
+            idx => {
+                return Err(TheoryError::Sample {
+                    what: "modulation",
+                    idx,
+                })
+            }
         })
     }
 }
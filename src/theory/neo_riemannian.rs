@@ -0,0 +1,209 @@
+use colored::*;
+use log::{debug, info};
+use rand::prelude::*;
+use statrs::distribution::Categorical;
+use std::fmt;
+
+use crate::app::Difficulty;
+use super::{
+    chord::{Chord, ChordType, Voicing},
+    error::TheoryError,
+    key::{Key, KeyType},
+    tone::{Interval, Tone},
+};
+
+/// Neo-Riemannian transformations, each one moving a major or minor triad to
+/// one of the opposite quality by shifting as few voices as possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Transform {
+    Parallel,       // P: C <-> Cm
+    Leittonwechsel, // L: C <-> Em
+    Relative,       // R: C <-> Am
+    Slide,          // S: C <-> C#m, the third is held
+    Nebenverwandt,  // N = RLP: C <-> Fm
+    Hexatonic,      // H = LPL: C <-> Abm
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transform::Parallel => {
+                write!(f, "{}", self.symbol().white().bold())
+            }
+            Transform::Leittonwechsel => {
+                write!(f, "{}", self.symbol().green().bold())
+            }
+            Transform::Relative => {
+                write!(f, "{}", self.symbol().blue().bold())
+            }
+            Transform::Slide => {
+                write!(f, "{}", self.symbol().yellow().bold())
+            }
+            Transform::Nebenverwandt => {
+                write!(f, "{}", self.symbol().purple().bold())
+            }
+            Transform::Hexatonic => {
+                write!(f, "{}", self.symbol().red().bold())
+            }
+        }
+    }
+}
+
+impl Transform {
+    pub(crate) fn all_vec() -> Vec<Self> {
+        vec![
+            Transform::Parallel,
+            Transform::Leittonwechsel,
+            Transform::Relative,
+            Transform::Slide,
+            Transform::Nebenverwandt,
+            Transform::Hexatonic,
+        ]
+    }
+
+    pub(crate) fn symbol(&self) -> &'static str {
+        match self {
+            Transform::Parallel => "P",
+            Transform::Leittonwechsel => "L",
+            Transform::Relative => "R",
+            Transform::Slide => "S",
+            Transform::Nebenverwandt => "N",
+            Transform::Hexatonic => "H",
+        }
+    }
+
+    /// Sample a transformation other than the previous one: every one of them
+    /// undoes itself, so repeating it would walk straight back.
+    pub(crate) fn sample(previous: Option<&Transform>) -> Result<Self, TheoryError> {
+        let mut rng_seed = rand::thread_rng();
+        let all = Self::all_vec();
+        let prob: Vec<f64> = all
+            .iter()
+            .map(|e| match previous {
+                Some(pre) if pre == e => 0.0,
+                _ => 1.0,
+            })
+            .collect();
+
+        let mnm = Categorical::new(&prob)?;
+        let idx = mnm.sample(&mut rng_seed) as i32;
+        let transform = all.get(idx as usize).cloned().ok_or(TheoryError::Sample {
+            what: "transformation",
+            idx,
+        })?;
+        debug!("Transform::sample(): {} sampled", transform);
+        Ok(transform)
+    }
+
+    /// Root interval from a major (or minor) triad to its image.
+    fn interval(&self, major: bool) -> Interval {
+        match (self, major) {
+            (Transform::Parallel, _) => Interval::PerfectUnison,
+            (Transform::Leittonwechsel, true) => Interval::MajorThird,
+            (Transform::Leittonwechsel, false) => Interval::MinorSixth,
+            (Transform::Relative, true) => Interval::MajorSixth,
+            (Transform::Relative, false) => Interval::MinorThird,
+            (Transform::Slide, true) => Interval::MinorSecond,
+            (Transform::Slide, false) => Interval::MajorSeventh,
+            (Transform::Nebenverwandt, true) => Interval::PerfectFourth,
+            (Transform::Nebenverwandt, false) => Interval::PerfectFifth,
+            (Transform::Hexatonic, true) => Interval::MinorSixth,
+            (Transform::Hexatonic, false) => Interval::MajorThird,
+        }
+    }
+
+    /// Root and quality of the image. A seventh chord moves its triad and
+    /// keeps the size of its seventh, so C7 goes to Cm7 under P and CM7 to CmM7.
+    fn target(&self, chord: &Chord) -> Result<(Tone, ChordType), TheoryError> {
+        let (major, major_seventh) = match chord.chord_type {
+            ChordType::Major => (true, None),
+            ChordType::Minor => (false, None),
+            ChordType::Major7 => (true, Some(true)),
+            ChordType::Dominant7 => (true, Some(false)),
+            ChordType::MinorMajor7 => (false, Some(true)),
+            ChordType::Minor7 => (false, Some(false)),
+            _ => {
+                return Err(TheoryError::NotTriadic(format!(
+                    "{}{}",
+                    chord.tonic, chord.chord_type
+                )))
+            }
+        };
+        let chord_type = match (!major, major_seventh) {
+            (true, None) => ChordType::Major,
+            (false, None) => ChordType::Minor,
+            (true, Some(true)) => ChordType::Major7,
+            (true, Some(false)) => ChordType::Dominant7,
+            (false, Some(true)) => ChordType::MinorMajor7,
+            (false, Some(false)) => ChordType::Minor7,
+        };
+        Ok((chord.tonic.add_interval(self.interval(major))?, chord_type))
+    }
+
+    pub(crate) fn apply(
+        &self,
+        chord: &Chord,
+        difficulty: Difficulty,
+    ) -> Result<Chord, TheoryError> {
+        let (tonic, chord_type) = self.target(chord)?;
+        info!(
+            "Transform::apply(): {} on {}{}",
+            self, chord.tonic, chord.chord_type
+        );
+        Chord::new(tonic, chord_type, Voicing::sample(difficulty)?)
+    }
+
+    /// The transformation taking one chord to the other, if there is one.
+    pub(crate) fn between(from: &Chord, to: &Chord) -> Option<Self> {
+        Self::all_vec().into_iter().find(|e| {
+            e.target(from).is_ok_and(|(tonic, chord_type)| {
+                tonic.idx == to.tonic.idx && chord_type == to.chord_type
+            })
+        })
+    }
+}
+
+/// Walk the Tonnetz from the chord by a number of random transformations.
+/// The chords are returned target first, as detours build them.
+pub(crate) fn walk(
+    chord: &Chord,
+    steps: usize,
+    difficulty: Difficulty,
+) -> Result<Vec<Chord>, TheoryError> {
+    let mut chords: Vec<Chord> = Vec::new();
+    let mut transform: Option<Transform> = None;
+    for _ in 0..steps {
+        let pre = chords.last().unwrap_or(chord);
+        let next = Transform::sample(transform.as_ref())?;
+        chords.push(next.apply(pre, difficulty.clone())?);
+        transform = Some(next);
+    }
+    chords.reverse();
+    Ok(chords)
+}
+
+/// Key a chord of the walk is heard in: major triads as their own Ionian,
+/// minor ones as their own Aeolian.
+pub(crate) fn home_key(chord: &Chord) -> Key {
+    let key_type = match chord.chord_type.is_minor() {
+        true => KeyType::Aeolian,
+        false => KeyType::Ionian,
+    };
+    Key::new(chord.tonic.clone(), key_type)
+}
+
+/// Label of every chord in playing order with the transformation leading
+/// on to the next one, e.g. "L ->"; the last chord has none.
+pub(crate) fn labels(chords: &[Chord]) -> Vec<String> {
+    let mut labels: Vec<String> = chords
+        .windows(2)
+        .map(|pair| match Transform::between(&pair[0], &pair[1]) {
+            Some(transform) => format!("{} ->", transform.symbol()),
+            None => "? ->".to_string(),
+        })
+        .collect();
+    if !chords.is_empty() {
+        labels.push(String::new());
+    }
+    labels
+}
@@ -0,0 +1,263 @@
+use log::debug;
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::app::Difficulty;
+use super::{
+    chord::{Chord, ChordType, Voicing, VoicingType},
+    key::{Key, KeyType},
+    tone::{NeutralTone, Tone, ToneVariant},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    InvalidTone(String),
+    UnknownChordType(String),
+    UnknownKeyType(String),
+    BassNotInChord { bass: String, chord: String },
+    RespelledTonic { tonic: String, spelled: String },
+    Unspellable(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => {
+                write!(f, "nothing to parse")
+            }
+            ParseError::InvalidTone(s) => {
+                write!(
+                    f,
+                    "'{}' is not a tone, expected a letter A-G with #, b, ## or bb",
+                    s
+                )
+            }
+            ParseError::UnknownChordType(s) => {
+                write!(
+                    f,
+                    "'{}' is not a known chord type (e.g. M7, m7, 7, m7b5, dim7)",
+                    s
+                )
+            }
+            ParseError::UnknownKeyType(s) => {
+                write!(
+                    f,
+                    "'{}' is not a known mode (e.g. ionian, dorian, harmonic minor)",
+                    s
+                )
+            }
+            ParseError::BassNotInChord { bass, chord } => {
+                write!(
+                    f,
+                    "bass '{}' is not one of the chord tones of '{}'",
+                    bass, chord
+                )
+            }
+            ParseError::RespelledTonic { tonic, spelled } => {
+                write!(f, "the mode on '{}' is spelled from '{}'", tonic, spelled)
+            }
+            ParseError::Unspellable(s) => {
+                write!(f, "'{}' needs more than a double accidental", s)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Close position piano voicing in the inversion, for chords written by name.
+fn close(inversion: usize) -> Voicing {
+    Voicing::new(VoicingType::Close, inversion, &Difficulty::Piano)
+}
+
+/// Split a leading tone name ("Bb", "F#", "Fx", "C") from the rest of the string.
+fn split_tone(s: &str) -> (&str, &str) {
+    let mut chars = s.char_indices();
+    if chars.next().is_none() {
+        return (s, "");
+    }
+    let end = chars
+        .find(|(_, c)| {
+            !matches!(
+                c,
+                '#' | 'b' | 'x' | '\u{266F}' | '\u{266D}' | '\u{1D12A}' | '\u{1D12B}'
+            )
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+impl FromStr for Tone {
+    type Err = ParseError;
+
+    /// Parse "C", "F#", "Bb", "F##" or "Ebb" (also accepting "x" and the unicode
+    /// sharp, flat, double sharp and double flat signs).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let letter = chars.next().ok_or(ParseError::Empty)?;
+        let tone = match letter.to_ascii_uppercase() {
+            'C' => NeutralTone::C,
+            'D' => NeutralTone::D,
+            'E' => NeutralTone::E,
+            'F' => NeutralTone::F,
+            'G' => NeutralTone::G,
+            'A' => NeutralTone::A,
+            'B' => NeutralTone::B,
+            _ => return Err(ParseError::InvalidTone(s.to_string())),
+        };
+        let accidentals: String = chars.collect();
+        let variant = match accidentals.as_str() {
+            "" => ToneVariant::Neutral,
+            "#" | "\u{266F}" => ToneVariant::Sharp,
+            "b" | "\u{266D}" => ToneVariant::Flat,
+            "##" | "x" | "\u{266F}\u{266F}" | "\u{1D12A}" => ToneVariant::DoubleSharp,
+            "bb" | "\u{266D}\u{266D}" | "\u{1D12B}" => ToneVariant::DoubleFlat,
+            _ => return Err(ParseError::InvalidTone(s.to_string())),
+        };
+        Ok(Tone::new(tone, variant))
+    }
+}
+
+impl FromStr for ChordType {
+    type Err = ParseError;
+
+    /// Parse the quality part of a chord symbol, e.g. "m7b5" or "maj9".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "" | "M" | "maj" => ChordType::Major,
+            "m" | "min" | "-" => ChordType::Minor,
+            "M7" | "maj7" | "Maj7" | "\u{0394}7" | "\u{0394}" | "^7" => ChordType::Major7,
+            "m7" | "min7" | "-7" => ChordType::Minor7,
+            "7" | "dom7" => ChordType::Dominant7,
+            "m7b5" | "-7b5" | "\u{00F8}" | "\u{00F8}7" => ChordType::HalfDiminished7,
+            "dim7" | "\u{00B0}7" | "o7" => ChordType::Diminished7,
+            "6" | "M6" | "maj6" => ChordType::Major6,
+            "m6" | "min6" | "-6" => ChordType::Minor6,
+            "M9" | "maj9" | "Maj9" | "\u{0394}9" => ChordType::Major9,
+            "m9" | "min9" | "-9" => ChordType::Minor9,
+            "9" => ChordType::Dominant9,
+            "11" => ChordType::Dominant11,
+            "13" => ChordType::Dominant13,
+            "7sus4" | "7sus" => ChordType::Dominant7Sus4,
+            "7b9" => ChordType::Dominant7Flat9,
+            "7#9" => ChordType::Dominant7Sharp9,
+            "7#11" => ChordType::Dominant7Sharp11,
+            "7b13" => ChordType::Dominant7Flat13,
+            "7alt" | "alt" => ChordType::Altered,
+            "mM7" | "mMaj7" | "m(maj7)" | "-\u{0394}7" => ChordType::MinorMajor7,
+            "aug" | "+" => ChordType::Augmented,
+            "aug7" | "+7" | "7#5" => ChordType::Augmented7,
+            "augM7" | "+M7" | "maj7#5" | "M7#5" => ChordType::AugmentedMajor7,
+            _ => return Err(ParseError::UnknownChordType(s.to_string())),
+        })
+    }
+}
+
+impl FromStr for KeyType {
+    type Err = ParseError;
+
+    /// Parse a mode name, ignoring case, spaces, dashes and underscores.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_lowercase();
+        Ok(match name.as_str() {
+            "ionian" | "major" => KeyType::Ionian,
+            "dorian" => KeyType::Dorian,
+            "phrygian" => KeyType::Phrygian,
+            "lydian" => KeyType::Lydian,
+            "mixolydian" => KeyType::Mixolydian,
+            "aeolian" | "minor" | "naturalminor" => KeyType::Aeolian,
+            "locrian" => KeyType::Locrian,
+            "harmonicminor" => KeyType::HarmonicMinor,
+            "locriannatural6" | "locrian#6" => KeyType::LocrianNatural6,
+            "ionianaugmented" | "ionian#5" => KeyType::IonianAugmented,
+            "doriansharp4" | "dorian#4" => KeyType::DorianSharp4,
+            "phrygiandominant" => KeyType::PhrygianDominant,
+            "lydiansharp2" | "lydian#2" => KeyType::LydianSharp2,
+            "ultralocrian" => KeyType::Ultralocrian,
+            "melodicminor" | "jazzminor" => KeyType::MelodicMinor,
+            "dorianflat2" | "dorianb2" => KeyType::DorianFlat2,
+            "lydianaugmented" | "lydian#5" => KeyType::LydianAugmented,
+            "lydiandominant" => KeyType::LydianDominant,
+            "mixolydianflat6" | "mixolydianb6" => KeyType::MixolydianFlat6,
+            "locriannatural2" | "locrian#2" => KeyType::LocrianNatural2,
+            "altered" | "superlocrian" => KeyType::Altered,
+            _ => return Err(ParseError::UnknownKeyType(s.trim().to_string())),
+        })
+    }
+}
+
+impl FromStr for Key {
+    type Err = ParseError;
+
+    /// Parse "Eb dorian" or "F# harmonic minor"; a bare tonic means Ionian.
+    /// Keys are spelled from the tonics `Key::new` picks, so a tonic written
+    /// otherwise (C# major for Db major) is an error rather than respelled.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (tonic, mode) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let tonic: Tone = tonic.parse()?;
+        let key_type = match mode.trim() {
+            "" => KeyType::Ionian,
+            mode => mode.parse()?,
+        };
+        let key = Key::new(tonic.clone(), key_type);
+        if key.tonic != tonic {
+            return Err(ParseError::RespelledTonic {
+                tonic: tonic.to_string(),
+                spelled: key.tonic.to_string(),
+            });
+        }
+        Ok(key)
+    }
+}
+
+impl FromStr for Chord {
+    type Err = ParseError;
+
+    /// Parse "F#7" or "Bbm7b5/Ab"; a slash bass selects the close position inversion.
+    /// The root keeps the spelling it was written with.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let (symbol, bass) = match s.rsplit_once('/') {
+            Some((symbol, bass)) => (symbol, Some(bass)),
+            None => (s, None),
+        };
+        let (tonic, chord_type) = split_tone(symbol);
+        let tonic: Tone = tonic.parse()?;
+        let chord_type: ChordType = chord_type.parse()?;
+        debug!("Chord::from_str(): {} parsed as {}{}", s, tonic, chord_type);
+        let unspellable = |_| ParseError::Unspellable(symbol.to_string());
+
+        let inversion = match bass {
+            None => 0,
+            Some(bass_str) => {
+                let bass: Tone = bass_str.parse()?;
+                let root_position = Chord::spelled(tonic.clone(), chord_type.clone(), close(0))
+                    .map_err(unspellable)?;
+                let core_len = chord_type.intervals().0.len();
+                match root_position.tones[..core_len]
+                    .iter()
+                    .position(|e| e.idx == bass.idx)
+                {
+                    Some(position) => position,
+                    None => {
+                        return Err(ParseError::BassNotInChord {
+                            bass: bass_str.trim().to_string(),
+                            chord: symbol.to_string(),
+                        })
+                    }
+                }
+            }
+        };
+        Chord::spelled(tonic, chord_type, close(inversion)).map_err(unspellable)
+    }
+}
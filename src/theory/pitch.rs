@@ -0,0 +1,91 @@
+use colored::*;
+use std::fmt;
+
+use super::tone::Tone;
+
+/// Lowest note a piano voicing starts from, C3.
+pub(crate) const PIANO_FLOOR: u8 = 48;
+/// Lowest note of a guitar in standard tuning, E2.
+pub(crate) const GUITAR_FLOOR: u8 = 40;
+
+/// A tone in register: the MIDI note number together with its spelling.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Pitch {
+    pub(crate) midi: u8,
+    pub(crate) tone: Tone,
+}
+
+impl Pitch {
+    /// Pitch of the tone in a scientific octave, e.g. `Eb` in octave 4 is 63.
+    /// The octave follows the letter, so B#3 is 60 and Cb4 is 59.
+    pub(crate) fn new(tone: Tone, octave: i8) -> Self {
+        let natural = (tone.idx - 1 - tone.alter()).rem_euclid(12);
+        let midi = (octave as i16 + 1) * 12 + natural as i16 + tone.alter() as i16;
+        Pitch {
+            midi: midi.clamp(0, 127) as u8,
+            tone,
+        }
+    }
+
+    /// Pitch of a MIDI note, spelled plainly (sharps for the black keys).
+    pub(crate) fn from_midi(midi: u8) -> Self {
+        Pitch {
+            midi,
+            tone: Tone::from_idx(Self::class_of(midi)),
+        }
+    }
+
+    /// Pitch class (1-12) of a MIDI note, C = 1.
+    pub(crate) fn class_of(midi: u8) -> i8 {
+        (midi % 12) as i8 + 1
+    }
+
+    pub(crate) fn class(&self) -> i8 {
+        Self::class_of(self.midi)
+    }
+
+    /// Scientific octave, following the letter (C4 is middle C).
+    pub(crate) fn octave(&self) -> i8 {
+        (self.midi as i8 - self.tone.alter()).div_euclid(12) - 1
+    }
+
+    /// Diatonic staff step counted from C0, used to place the note on a staff.
+    pub(crate) fn staff_step(&self) -> i16 {
+        self.octave() as i16 * 7 + self.tone.letter_step() as i16
+    }
+
+    /// Stack the tones bottom to top: the first one is placed at or above the
+    /// floor, every next one is the closest pitch above the previous one.
+    pub(crate) fn stack(tones: &[Tone], floor: u8) -> Vec<Pitch> {
+        let mut pitches: Vec<Pitch> = Vec::with_capacity(tones.len());
+        for tone in tones.iter() {
+            let above = match pitches.last() {
+                Some(pre) => pre.midi + 1,
+                None => floor,
+            };
+            let offset = (tone.idx - Self::class_of(above)).rem_euclid(12) as u8;
+            pitches.push(Pitch {
+                midi: above + offset,
+                tone: tone.clone(),
+            });
+        }
+        pitches
+    }
+}
+
+impl fmt::Display for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.tone,
+            self.octave().to_string().white().bold()
+        )
+    }
+}
+
+impl fmt::Debug for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.tone, self.octave())
+    }
+}
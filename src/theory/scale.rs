@@ -0,0 +1,186 @@
+use colored::*;
+use log::info;
+use rand::prelude::*;
+use statrs::distribution::Categorical;
+use std::fmt;
+
+use crate::app::Difficulty;
+use super::{
+    error::TheoryError,
+    key::{Key, KeyType},
+    pitch::Pitch,
+    tone::{Interval, Tone},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ScaleType {
+    Mode(KeyType),
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+    WholeTone,
+    HalfWhole, // octatonic starting with a half step
+    WholeHalf, // octatonic starting with a whole step
+}
+
+impl fmt::Display for ScaleType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScaleType::Mode(key_type) => {
+                write!(f, "{}", key_type)
+            }
+            ScaleType::MajorPentatonic => {
+                write!(f, "{}", "MajorPentatonic".green().bold())
+            }
+            ScaleType::MinorPentatonic => {
+                write!(f, "{}", "MinorPentatonic".blue().bold())
+            }
+            ScaleType::Blues => {
+                write!(f, "{}", "Blues".blue().bold().italic())
+            }
+            ScaleType::WholeTone => {
+                write!(f, "{}", "WholeTone".cyan().bold())
+            }
+            ScaleType::HalfWhole => {
+                write!(f, "{}", "HalfWhole".red().bold())
+            }
+            ScaleType::WholeHalf => {
+                write!(f, "{}", "WholeHalf".purple().bold())
+            }
+        }
+    }
+}
+
+impl ScaleType {
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
+        let mut rng_seed = rand::thread_rng();
+        // modes are the bread and butter, the rest is seasoning
+        let prob = match difficulty {
+            Difficulty::Piano => [6.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0],
+            Difficulty::Guitar => [6.0, 2.0, 2.0, 2.0, 1.0, 1.0, 1.0],
+        };
+
+        let mnm = Categorical::new(&prob)?;
+        Ok(match mnm.sample(&mut rng_seed) as i32 {
+            0 => ScaleType::Mode(KeyType::sample(difficulty)?),
+            1 => ScaleType::MajorPentatonic,
+            2 => ScaleType::MinorPentatonic,
+            3 => ScaleType::Blues,
+            4 => ScaleType::WholeTone,
+            5 => ScaleType::HalfWhole,
+            6 => ScaleType::WholeHalf,
+            idx => return Err(TheoryError::Sample { what: "scale", idx }),
+        })
+    }
+
+    /// Spelled intervals of the degrees above the tonic.
+    pub(crate) fn interval_vec(&self) -> Vec<Interval> {
+        use Interval::*;
+        match self {
+            ScaleType::Mode(key_type) => key_type.interval_vec(),
+            ScaleType::MajorPentatonic => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                PerfectFifth,
+                MajorSixth,
+            ],
+            ScaleType::MinorPentatonic => vec![
+                PerfectUnison,
+                MinorThird,
+                PerfectFourth,
+                PerfectFifth,
+                MinorSeventh,
+            ],
+            ScaleType::Blues => vec![
+                PerfectUnison,
+                MinorThird,
+                PerfectFourth,
+                AugmentedFourth,
+                PerfectFifth,
+                MinorSeventh,
+            ],
+            ScaleType::WholeTone => vec![
+                PerfectUnison,
+                MajorSecond,
+                MajorThird,
+                AugmentedFourth,
+                AugmentedFifth,
+                MinorSeventh,
+            ],
+            ScaleType::HalfWhole => vec![
+                PerfectUnison,
+                MinorSecond,
+                MinorThird,
+                MajorThird,
+                AugmentedFourth,
+                PerfectFifth,
+                MajorSixth,
+                MinorSeventh,
+            ],
+            ScaleType::WholeHalf => vec![
+                PerfectUnison,
+                MajorSecond,
+                MinorThird,
+                PerfectFourth,
+                DiminishedFifth,
+                MinorSixth,
+                MajorSixth,
+                MajorSeventh,
+            ],
+        }
+    }
+}
+
+/// A scale spelled from its tonic, degrees in ascending order within an octave.
+#[derive(Debug, Clone)]
+pub(crate) struct Scale {
+    pub(crate) tonic: Tone,
+    pub(crate) scale_type: ScaleType,
+    pub(crate) tones: Vec<Tone>,
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.tonic, self.scale_type)
+    }
+}
+
+impl Scale {
+    pub(crate) fn new(tonic: Tone, scale_type: ScaleType) -> Result<Self, TheoryError> {
+        let tones = scale_type
+            .interval_vec()
+            .into_iter()
+            .map(|e| tonic.add_interval(e))
+            .collect::<Result<Vec<Tone>, TheoryError>>()?;
+        Ok(Scale {
+            tonic,
+            scale_type,
+            tones,
+        })
+    }
+
+    /// A sampled scale type on the tonic of a sampled key, modes spelled as
+    /// the key of that mode would spell them and other scales from the tonic
+    /// of the major key.
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
+        let key = Key::sample(difficulty.clone())?;
+        let scale = match ScaleType::sample(difficulty)? {
+            ScaleType::Mode(key_type) => Key::new(key.tonic, key_type).scale()?,
+            scale_type => Scale::new(Key::new(key.tonic, KeyType::Ionian).tonic, scale_type)?,
+        };
+        info!("Scale::sample(): {} sampled", scale);
+        Ok(scale)
+    }
+
+    /// The scale up to the tonic an octave higher and back down, starting
+    /// at or above the floor.
+    pub(crate) fn up_and_down(&self, floor: u8) -> Vec<Pitch> {
+        let mut tones = self.tones.clone();
+        tones.push(self.tonic.clone());
+        let mut pitches = Pitch::stack(&tones, floor);
+        let down: Vec<Pitch> = pitches.iter().rev().skip(1).cloned().collect();
+        pitches.extend(down);
+        pitches
+    }
+}
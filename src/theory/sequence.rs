@@ -0,0 +1,378 @@
+use colored::*;
+use log::debug;
+use std::{collections::VecDeque, fmt};
+
+use super::{chord::ChordType, error::TheoryError, key::Key};
+
+/// A change from one scale degree to another.
+pub(crate) type Transition = (i8, i8);
+
+/// Which changes between degrees the functional drill goes through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Changes {
+    All,      // every change between the seven degrees
+    Fifths,   // roots a fourth or a fifth apart
+    Steps,    // roots a second apart
+    TwoFives, // every change between the minor sevenths and dominants of the key
+}
+
+impl fmt::Display for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Changes::All => {
+                write!(f, "{}", "every change".white().bold())
+            }
+            Changes::Fifths => {
+                write!(f, "{}", "by fifths".green().bold())
+            }
+            Changes::Steps => {
+                write!(f, "{}", "by steps".blue().bold())
+            }
+            Changes::TwoFives => {
+                write!(f, "{}", "minor sevenths and dominants".yellow().bold())
+            }
+        }
+    }
+}
+
+impl Changes {
+    pub(crate) fn all_vec() -> Vec<Self> {
+        vec![
+            Changes::All,
+            Changes::Fifths,
+            Changes::Steps,
+            Changes::TwoFives,
+        ]
+    }
+
+    /// The transitions to cover, with the chord types read in the key.
+    pub(crate) fn transitions(&self, key: &Key) -> Result<Vec<Transition>, TheoryError> {
+        let all = [1, 2, 3, 4, 5, 6, 7];
+        let apart = |from: i8, to: i8| (to - from).rem_euclid(7);
+        Ok(match self {
+            Changes::All => transitions(&all, |_, _| true),
+            Changes::Fifths => transitions(&all, |from, to| matches!(apart(from, to), 3 | 4)),
+            Changes::Steps => transitions(&all, |from, to| matches!(apart(from, to), 1 | 6)),
+            Changes::TwoFives => {
+                let degrees = degrees_with(key, &[ChordType::Minor7, ChordType::Dominant7])?;
+                transitions(&degrees, |_, _| true)
+            }
+        })
+    }
+}
+
+/// Every change between two different degrees that the constraint allows.
+pub(crate) fn transitions(degrees: &[i8], allowed: impl Fn(i8, i8) -> bool) -> Vec<Transition> {
+    let mut transitions = Vec::new();
+    for &from in degrees.iter() {
+        for &to in degrees.iter() {
+            if from != to && allowed(from, to) {
+                transitions.push((from, to));
+            }
+        }
+    }
+    transitions
+}
+
+/// Degrees whose seventh chord in the key is one of the chord types, so the
+/// key type picks the mode family, e.g. the ii, iii, V and vi of a major key
+/// for minor sevenths and dominants.
+pub(crate) fn degrees_with(key: &Key, chord_types: &[ChordType]) -> Result<Vec<i8>, TheoryError> {
+    let mut degrees = Vec::new();
+    for idx in 1..=7 {
+        if chord_types.contains(&key.gen_chord_type(idx)?) {
+            degrees.push(idx);
+        }
+    }
+    Ok(degrees)
+}
+
+/// Shortest sequence of degrees whose consecutive pairs take every one of
+/// the transitions. Degrees that are left or entered more often than the
+/// other way round are joined by the cheapest detours over the transitions,
+/// and what is left is walked in one Eulerian path. Where the transitions
+/// offer no way between two degrees, the fewest direct changes outside them
+/// are added. A sequence that can wrap around ends on the degree it starts from.
+pub(crate) fn covering(transitions: &[Transition]) -> Vec<i8> {
+    let mut edges: Vec<Transition> = transitions.to_vec();
+    edges.sort();
+    edges.dedup();
+    let Some(&(first, _)) = edges.first() else {
+        return Vec::new();
+    };
+
+    // degrees entered more often than left need a way out, and the other way round
+    let mut balance: Vec<(i8, i32)> = Vec::new();
+    for &(from, to) in edges.iter() {
+        for (degree, delta) in [(from, 1), (to, -1)] {
+            match balance.iter_mut().find(|e| e.0 == degree) {
+                Some(e) => e.1 += delta,
+                None => balance.push((degree, delta)),
+            }
+        }
+    }
+    let needs_out: Vec<i8> = unit_list(&balance, |e| -e);
+    let needs_in: Vec<i8> = unit_list(&balance, |e| e);
+
+    // join them pairwise, leaving one of each free to start and end on
+    let pairs = cheapest_pairs(&edges, &needs_out, &needs_in);
+    for (from, to) in pairs.into_iter() {
+        let detour = shortest_path(&edges, from, to).unwrap_or(vec![from, to]);
+        edges.extend(detour.windows(2).map(|e| (e[0], e[1])));
+    }
+
+    // an open path starts where the edges still leave more often than enter
+    let start = balance
+        .iter()
+        .map(|e| e.0)
+        .find(|&degree| {
+            let out = edges.iter().filter(|e| e.0 == degree).count();
+            let into = edges.iter().filter(|e| e.1 == degree).count();
+            out > into
+        })
+        .unwrap_or(first);
+    // transitions apart from the walk are closed rounds of their own, each
+    // entered by one more change from where the walk ends
+    let mut sequence = eulerian_path(&edges, start);
+    while sequence.len() != edges.len() + 1 {
+        let mut unused = edges.clone();
+        for taken in sequence.windows(2) {
+            if let Some(i) = unused
+                .iter()
+                .position(|e| e.0 == taken[0] && e.1 == taken[1])
+            {
+                unused.remove(i);
+            }
+        }
+        let (Some(&end), Some(&(next, _))) = (sequence.last(), unused.first()) else {
+            break;
+        };
+        debug!("sequence::covering(): joining {} to {}", end, next);
+        edges.push((end, next));
+        sequence = eulerian_path(&edges, start);
+    }
+    debug!("sequence::covering(): {:?}", sequence);
+    sequence
+}
+
+/// How well a sequence covers the transitions.
+#[derive(Debug, Clone)]
+pub(crate) struct Coverage {
+    pub(crate) covered: Vec<Transition>,
+    pub(crate) missing: Vec<Transition>,
+    pub(crate) steps: usize, // changes in the sequence, a covering one needs at least one per transition
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let missing: Vec<String> = self
+            .missing
+            .iter()
+            .map(|(from, to)| format!("{}->{}", from, to))
+            .collect();
+        write!(
+            f,
+            "{}/{} transitions in {} steps",
+            self.covered.len(),
+            self.covered.len() + self.missing.len(),
+            self.steps
+        )?;
+        if !missing.is_empty() {
+            write!(f, ", missing {}", missing.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Coverage {
+    pub(crate) fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Which of the transitions the sequence takes and which it misses.
+pub(crate) fn verify(sequence: &[i8], transitions: &[Transition]) -> Coverage {
+    let taken: Vec<Transition> = sequence.windows(2).map(|e| (e[0], e[1])).collect();
+    let (covered, missing) = transitions.iter().partition(|e| taken.contains(e));
+    Coverage {
+        covered,
+        missing,
+        steps: taken.len(),
+    }
+}
+
+/// Each degree repeated once for every unit of its weighted balance.
+fn unit_list(balance: &[(i8, i32)], weight: impl Fn(i32) -> i32) -> Vec<i8> {
+    balance
+        .iter()
+        .flat_map(|&(degree, b)| std::iter::repeat_n(degree, weight(b).max(0) as usize))
+        .collect()
+}
+
+/// Fewest transitions from one degree to another, both ends included.
+fn shortest_path(edges: &[Transition], from: i8, to: i8) -> Option<Vec<i8>> {
+    let mut parent: Vec<(i8, i8)> = Vec::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(degree) = queue.pop_front() {
+        if degree == to {
+            let mut path = vec![to];
+            let mut at = to;
+            while at != from {
+                at = parent.iter().find(|e| e.0 == at)?.1;
+                path.push(at);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &(_, next) in edges.iter().filter(|e| e.0 == degree) {
+            if next != from && !parent.iter().any(|e| e.0 == next) {
+                parent.push((next, degree));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Pair every degree that needs a way out with one that needs a way in,
+/// greedily taking the shortest detour left. A pair with no way between
+/// them over the edges costs one added change, which outweighs any detour.
+/// The dearest of each stays unpaired, as the start and the end of the path.
+fn cheapest_pairs(edges: &[Transition], needs_out: &[i8], needs_in: &[i8]) -> Vec<Transition> {
+    // changes added, then steps taken
+    type Cost = (usize, usize);
+    let cost = |from: i8, to: i8| -> Cost {
+        match shortest_path(edges, from, to) {
+            Some(path) => (0, path.len() - 1),
+            // inside one group of degrees it leaves the others to be joined later
+            None if component(edges, from).contains(&to) => (2, 1),
+            None => (1, 1),
+        }
+    };
+    let mut candidates: Vec<(Cost, usize, usize)> = Vec::new();
+    for (i, &from) in needs_out.iter().enumerate() {
+        for (j, &to) in needs_in.iter().enumerate() {
+            candidates.push((cost(from, to), i, j));
+        }
+    }
+    candidates.sort();
+
+    let mut out_taken = vec![false; needs_out.len()];
+    let mut in_taken = vec![false; needs_in.len()];
+    let mut pairs = Vec::new();
+    for (_, i, j) in candidates.into_iter() {
+        if pairs.len() + 1 >= needs_out.len().min(needs_in.len()) {
+            break;
+        }
+        if !out_taken[i] && !in_taken[j] {
+            out_taken[i] = true;
+            in_taken[j] = true;
+            pairs.push((needs_out[i], needs_in[j]));
+        }
+    }
+    pairs
+}
+
+/// Degrees reached from the degree over the edges taken either way.
+fn component(edges: &[Transition], degree: i8) -> Vec<i8> {
+    let mut reached = vec![degree];
+    let mut queue = VecDeque::from([degree]);
+    while let Some(at) = queue.pop_front() {
+        for &(from, to) in edges.iter() {
+            let next = match (from == at, to == at) {
+                (true, _) => to,
+                (_, true) => from,
+                _ => continue,
+            };
+            if !reached.contains(&next) {
+                reached.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+/// Walk every edge once from the start, by Hierholzer's algorithm.
+fn eulerian_path(edges: &[Transition], start: i8) -> Vec<i8> {
+    let mut unused: Vec<Transition> = edges.to_vec();
+    let mut stack = vec![start];
+    let mut path = Vec::new();
+    while let Some(&degree) = stack.last() {
+        match unused.iter().position(|e| e.0 == degree) {
+            Some(i) => {
+                let (_, next) = unused.remove(i);
+                stack.push(next);
+            }
+            None => {
+                path.push(degree);
+                stack.pop();
+            }
+        }
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covers(transitions: &[Transition]) -> Coverage {
+        verify(&covering(transitions), transitions)
+    }
+
+    #[test]
+    fn every_change_between_the_degrees_in_one_step_each() {
+        let transitions = transitions(&[1, 2, 3, 4, 5, 6, 7], |_, _| true);
+        let coverage = covers(&transitions);
+        assert!(coverage.is_complete());
+        assert_eq!(coverage.steps, 42);
+    }
+
+    #[test]
+    fn rounds_through_one_degree_return_with_added_changes() {
+        let transitions = [(1, 2), (1, 3), (1, 4), (2, 5), (3, 5), (4, 5)];
+        let sequence = covering(&transitions);
+        let coverage = verify(&sequence, &transitions);
+        assert!(coverage.is_complete());
+        assert_eq!(coverage.steps, 8);
+        assert_eq!(sequence.first(), Some(&1));
+        assert_eq!(sequence.last(), Some(&5));
+    }
+
+    #[test]
+    fn closed_rounds_close_on_the_start() {
+        let transitions = transitions(&[1, 2, 3, 4, 5, 6, 7], |from, to| {
+            matches!((to - from).rem_euclid(7), 3 | 4)
+        });
+        let sequence = covering(&transitions);
+        let coverage = verify(&sequence, &transitions);
+        assert!(coverage.is_complete());
+        assert_eq!(coverage.steps, transitions.len());
+        assert_eq!(sequence.first(), sequence.last());
+    }
+
+    #[test]
+    fn separate_groups_of_degrees_are_joined() {
+        let transitions = [(1, 2), (2, 1), (5, 6), (6, 5)];
+        let coverage = covers(&transitions);
+        assert!(coverage.is_complete());
+        // one change leads from the one round into the other
+        assert_eq!(coverage.steps, 5);
+    }
+
+    #[test]
+    fn verify_reports_the_missing_transitions() {
+        let coverage = verify(&[1, 2, 5], &[(1, 2), (2, 5), (5, 1)]);
+        assert!(!coverage.is_complete());
+        assert_eq!(coverage.covered, vec![(1, 2), (2, 5)]);
+        assert_eq!(coverage.missing, vec![(5, 1)]);
+        assert_eq!(coverage.steps, 2);
+    }
+
+    #[test]
+    fn nothing_to_cover_is_an_empty_sequence() {
+        assert!(covering(&[]).is_empty());
+    }
+}
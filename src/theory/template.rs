@@ -0,0 +1,187 @@
+use colored::*;
+use log::{debug, info};
+use rand::prelude::*;
+use statrs::distribution::Categorical;
+use std::fmt;
+
+use crate::app::Difficulty;
+use super::{
+    analysis::NUMERALS,
+    chord::{Chord, ChordType, Voicing},
+    error::TheoryError,
+    key::{Key, KeyType},
+    tone::Interval,
+};
+
+/// Standard progressions, written in roman numerals relative to the tonic
+/// so that they can be played in any key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Template {
+    JazzBlues,       // 12 bars, with the #iv°7 and the turnarounds
+    MinorBlues,      // 12 bars, bVI7 - V7alt into the last line
+    RhythmChangesA,  // I - vi - ii - V, then I7 - IV - #iv°7 - I
+    RhythmChangesB,  // the bridge, dominants around the cycle of fifths
+    ColtraneChanges, // major thirds cycle of Giant Steps over ii - V - I
+    AutumnLeaves,    // ii - V chains into the relative major and minor
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Template::JazzBlues => {
+                write!(f, "{}", "JazzBlues".blue().bold())
+            }
+            Template::MinorBlues => {
+                write!(f, "{}", "MinorBlues".blue().bold().italic())
+            }
+            Template::RhythmChangesA => {
+                write!(f, "{}", "RhythmChangesA".green().bold())
+            }
+            Template::RhythmChangesB => {
+                write!(f, "{}", "RhythmChangesB".green().bold().italic())
+            }
+            Template::ColtraneChanges => {
+                write!(f, "{}", "ColtraneChanges".red().bold())
+            }
+            Template::AutumnLeaves => {
+                write!(f, "{}", "AutumnLeaves".yellow().bold())
+            }
+        }
+    }
+}
+
+impl Template {
+    pub(crate) fn sample(difficulty: Difficulty) -> Result<Self, TheoryError> {
+        let mut rng_seed = rand::thread_rng();
+        let prob = match difficulty {
+            Difficulty::Piano => [1.0; 6],
+            Difficulty::Guitar => [1.0; 6],
+        };
+
+        let mnm = Categorical::new(&prob)?;
+        let template = match mnm.sample(&mut rng_seed) as i32 {
+            0 => Template::JazzBlues,
+            1 => Template::MinorBlues,
+            2 => Template::RhythmChangesA,
+            3 => Template::RhythmChangesB,
+            4 => Template::ColtraneChanges,
+            5 => Template::AutumnLeaves,
+            idx => {
+                return Err(TheoryError::Sample {
+                    what: "template",
+                    idx,
+                })
+            }
+        };
+        info!("Template::sample(): {} sampled", template);
+        Ok(template)
+    }
+
+    /// Mode the numerals are read in, for the analysis of the chords.
+    pub(crate) fn key_type(&self) -> KeyType {
+        match self {
+            Template::MinorBlues => KeyType::Aeolian,
+            _ => KeyType::Ionian,
+        }
+    }
+
+    /// Phrases of the template, each played as one measure and ending on its
+    /// target. Flat and sharp degrees are counted on the major scale.
+    pub(crate) fn phrases(&self) -> Vec<&'static str> {
+        match self {
+            Template::JazzBlues => vec![
+                "I7 IV7 I7 v7 I7",
+                "IV7 #iv°7 I7 VI7",
+                "ii7 V7 I7 VI7 ii7 V7",
+            ],
+            Template::MinorBlues => vec!["i7 iv7 i7", "iv7 i7", "bVI7 V7alt i7 V7alt"],
+            Template::RhythmChangesA => vec![
+                "Imaj7 vi7 ii7 V7 Imaj7 vi7 ii7 V7",
+                "Imaj7 I7 IVmaj7 #iv°7 Imaj7 V7 Imaj7",
+            ],
+            Template::RhythmChangesB => vec!["III7 VI7", "II7 V7"],
+            Template::ColtraneChanges => vec!["ii7 bIII7 bVImaj7 VII7 IIImaj7 V7 Imaj7"],
+            Template::AutumnLeaves => vec!["ii7 V7 Imaj7 IVmaj7", "viiø7 III7 vi7"],
+        }
+    }
+
+    /// A random key for a chorus of the template, in the mode it is read in.
+    pub(crate) fn sample_key(&self, difficulty: Difficulty) -> Result<Key, TheoryError> {
+        let tonic = Key::sample(difficulty)?.degree_tone(1)?;
+        Ok(Key::new(tonic, self.key_type()))
+    }
+
+    /// Chords of a phrase in the key, stored target first as the detours are.
+    pub(crate) fn build_chords(
+        &self,
+        phrase: usize,
+        key: &Key,
+        difficulty: Difficulty,
+    ) -> Result<Vec<Chord>, TheoryError> {
+        let phrases = self.phrases();
+        let numerals = phrases
+            .get(phrase)
+            .ok_or(TheoryError::NoCandidate("phrase of the template"))?;
+        let mut chords = Vec::new();
+        for numeral in numerals.split_whitespace().rev() {
+            chords.push(numeral_chord(key, numeral, difficulty.clone())?);
+        }
+        debug!("Template::build_chords(): {} {} in {}", self, numerals, key);
+        Ok(chords)
+    }
+}
+
+/// Interval of a degree of the major scale above the tonic, moved a half
+/// step by the accidental in front of the numeral, e.g. bVI is a minor sixth.
+fn interval(degree: usize, alter: i8) -> Option<Interval> {
+    use Interval::*;
+    let intervals = [
+        [None, Some(PerfectUnison), None],
+        [Some(MinorSecond), Some(MajorSecond), Some(AugmentedSecond)],
+        [Some(MinorThird), Some(MajorThird), None],
+        [None, Some(PerfectFourth), Some(AugmentedFourth)],
+        [
+            Some(DiminishedFifth),
+            Some(PerfectFifth),
+            Some(AugmentedFifth),
+        ],
+        [Some(MinorSixth), Some(MajorSixth), None],
+        [Some(MinorSeventh), Some(MajorSeventh), None],
+    ];
+    intervals.get(degree)?.get((alter + 1) as usize)?.clone()
+}
+
+/// Chord of a roman numeral such as "bVI7", "#iv°7" or "V7alt" in the key.
+/// The case gives the triad, and a bare "7" or "maj7" stacks on it: V7 is a
+/// dominant, v7 a minor seventh and imaj7 a minor major seventh; any other
+/// suffix is read as a chord symbol.
+fn numeral_chord(key: &Key, numeral: &str, difficulty: Difficulty) -> Result<Chord, TheoryError> {
+    let unknown = || TheoryError::UnknownNumeral(numeral.to_string());
+    let (alter, rest) = match numeral.chars().next() {
+        Some('b') => (-1, &numeral[1..]),
+        Some('#') => (1, &numeral[1..]),
+        _ => (0, numeral),
+    };
+    let end = rest
+        .find(|c: char| !matches!(c, 'I' | 'V' | 'i' | 'v'))
+        .unwrap_or(rest.len());
+    let (roman, suffix) = rest.split_at(end);
+    let degree = NUMERALS
+        .iter()
+        .position(|e| e.eq_ignore_ascii_case(roman))
+        .ok_or_else(unknown)?;
+    let minor = roman.chars().all(|c| c.is_lowercase());
+    let chord_type = match (minor, suffix) {
+        (false, "") => ChordType::Major,
+        (true, "") => ChordType::Minor,
+        (false, "7") => ChordType::Dominant7,
+        (true, "7") => ChordType::Minor7,
+        (false, "maj7") => ChordType::Major7,
+        (true, "maj7") => ChordType::MinorMajor7,
+        (_, suffix) => suffix.parse().map_err(|_| unknown())?,
+    };
+    let root = key
+        .degree_tone(1)?
+        .add_interval(interval(degree, alter).ok_or_else(unknown)?)?;
+    Chord::new(root, chord_type, Voicing::sample(difficulty)?)
+}
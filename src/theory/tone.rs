@@ -1,20 +1,24 @@
-use super::{chord::ChordType, key::KeyType};
+use super::{chord::ChordType, error::TheoryError, key::KeyType};
 use colored::*;
 use log::{debug, info};
 use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum Interval {
     PerfectUnison,
     MinorSecond,
     MajorSecond,
+    AugmentedSecond,
     MinorThird,
     MajorThird,
+    DiminishedFourth,
     PerfectFourth,
     AugmentedFourth,
     DiminishedFifth,
     PerfectFifth,
+    AugmentedFifth,
     MinorSixth,
+    DiminishedSeventh,
     MajorSixth,
     MinorSeventh,
     MajorSeventh,
@@ -32,12 +36,18 @@ impl fmt::Display for Interval {
             Interval::MajorSecond => {
                 write!(f, "MajorSecond")
             }
+            Interval::AugmentedSecond => {
+                write!(f, "AugmentedSecond")
+            }
             Interval::MinorThird => {
                 write!(f, "MinorThird")
             }
             Interval::MajorThird => {
                 write!(f, "MajorThird")
             }
+            Interval::DiminishedFourth => {
+                write!(f, "DiminishedFourth")
+            }
             Interval::PerfectFourth => {
                 write!(f, "PerfectFourth")
             }
@@ -50,9 +60,15 @@ impl fmt::Display for Interval {
             Interval::PerfectFifth => {
                 write!(f, "PerfectFifth")
             }
+            Interval::AugmentedFifth => {
+                write!(f, "AugmentedFifth")
+            }
             Interval::MinorSixth => {
                 write!(f, "MinorSixth")
             }
+            Interval::DiminishedSeventh => {
+                write!(f, "DiminishedSeventh")
+            }
             Interval::MajorSixth => {
                 write!(f, "MajorSixth")
             }
@@ -67,36 +83,67 @@ impl fmt::Display for Interval {
 }
 
 impl Interval {
-    fn key_diff(&self) -> i8 {
+    pub(crate) fn key_diff(&self) -> i8 {
         match self {
             Interval::PerfectUnison => 0,
             Interval::MinorSecond => 1,
             Interval::MajorSecond => 2,
+            Interval::AugmentedSecond => 3,
             Interval::MinorThird => 3,
             Interval::MajorThird => 4,
+            Interval::DiminishedFourth => 4,
             Interval::PerfectFourth => 5,
             Interval::AugmentedFourth => 6,
             Interval::DiminishedFifth => 6,
             Interval::PerfectFifth => 7,
+            Interval::AugmentedFifth => 8,
             Interval::MinorSixth => 8,
+            Interval::DiminishedSeventh => 9,
             Interval::MajorSixth => 9,
             Interval::MinorSeventh => 10,
             Interval::MajorSeventh => 11,
         }
     }
 
+    /// Name as it is said, e.g. "minor 6th" or "augmented 4th".
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Interval::PerfectUnison => "unison",
+            Interval::MinorSecond => "minor 2nd",
+            Interval::MajorSecond => "major 2nd",
+            Interval::AugmentedSecond => "augmented 2nd",
+            Interval::MinorThird => "minor 3rd",
+            Interval::MajorThird => "major 3rd",
+            Interval::DiminishedFourth => "diminished 4th",
+            Interval::PerfectFourth => "perfect 4th",
+            Interval::AugmentedFourth => "augmented 4th",
+            Interval::DiminishedFifth => "diminished 5th",
+            Interval::PerfectFifth => "perfect 5th",
+            Interval::AugmentedFifth => "augmented 5th",
+            Interval::MinorSixth => "minor 6th",
+            Interval::DiminishedSeventh => "diminished 7th",
+            Interval::MajorSixth => "major 6th",
+            Interval::MinorSeventh => "minor 7th",
+            Interval::MajorSeventh => "major 7th",
+        }
+    }
+
     fn tone_diff(&self) -> i8 {
         match self {
             Interval::PerfectUnison => 0,
             Interval::MinorSecond => 1,
             Interval::MajorSecond => 1,
+            Interval::AugmentedSecond => 1,
             Interval::MinorThird => 2,
             Interval::MajorThird => 2,
+            Interval::DiminishedFourth => 3,
             Interval::PerfectFourth => 3,
             Interval::AugmentedFourth => 3,
             Interval::DiminishedFifth => 4,
             Interval::PerfectFifth => 4,
+            Interval::AugmentedFifth => 4,
             Interval::MinorSixth => 5,
+            Interval::DiminishedSeventh => 6,
             Interval::MajorSixth => 5,
             Interval::MinorSeventh => 6,
             Interval::MajorSeventh => 6,
@@ -226,6 +273,19 @@ impl NeutralTone {
         }
     }
 
+    /// Semitones above C of the natural, C = 0 .. B = 11.
+    fn semitone(&self) -> i8 {
+        match self {
+            NeutralTone::C => 0,
+            NeutralTone::D => 2,
+            NeutralTone::E => 4,
+            NeutralTone::F => 5,
+            NeutralTone::G => 7,
+            NeutralTone::A => 9,
+            NeutralTone::B => 11,
+        }
+    }
+
     fn add_interval(&self, interval: &Interval) -> Self {
         let v = self.derived_vec();
         v[interval.tone_diff() as usize].clone()
@@ -243,9 +303,11 @@ impl NeutralTone {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ToneVariant {
+    DoubleFlat,
     Flat,
     Neutral,
     Sharp,
+    DoubleSharp,
 }
 
 impl fmt::Display for ToneVariant {
@@ -260,6 +322,12 @@ impl fmt::Display for ToneVariant {
             ToneVariant::Flat => {
                 write!(f, "{}", "b".white().bold())
             }
+            ToneVariant::DoubleSharp => {
+                write!(f, "{}", "##".white().bold())
+            }
+            ToneVariant::DoubleFlat => {
+                write!(f, "{}", "bb".white().bold())
+            }
         }
     }
 }
@@ -278,34 +346,12 @@ impl Tone {
     }
 
     pub(crate) fn rematch_key(self, key_type: &KeyType) -> Tone {
-        let tonics = Vec::from([
-            Tone::new(NeutralTone::C, ToneVariant::Neutral),
-            Tone::new(NeutralTone::D, ToneVariant::Flat),
-            Tone::new(NeutralTone::D, ToneVariant::Neutral),
-            Tone::new(NeutralTone::E, ToneVariant::Flat),
-            Tone::new(NeutralTone::E, ToneVariant::Neutral),
-            Tone::new(NeutralTone::F, ToneVariant::Neutral),
-            Tone::new(NeutralTone::F, ToneVariant::Sharp),
-            Tone::new(NeutralTone::G, ToneVariant::Flat),
-            Tone::new(NeutralTone::G, ToneVariant::Neutral),
-            Tone::new(NeutralTone::A, ToneVariant::Flat),
-            Tone::new(NeutralTone::A, ToneVariant::Neutral),
-            Tone::new(NeutralTone::B, ToneVariant::Flat),
-            Tone::new(NeutralTone::B, ToneVariant::Neutral),
-        ]);
-        let interval = match key_type {
-            KeyType::Ionian => Interval::PerfectUnison,
-            KeyType::Dorian => Interval::MajorSecond,
-            KeyType::Phrygian => Interval::MajorThird,
-            KeyType::Lydian => Interval::PerfectFourth,
-            KeyType::Mixolydian => Interval::PerfectFifth,
-            KeyType::Aeolian => Interval::MajorSixth,
-            KeyType::Locrian => Interval::MajorSeventh,
-        };
+        let tonics = key_type.parent_tonic_vec();
+        let interval = key_type.family_vec()[0].interval_vec()[key_type.family_idx()].clone();
         info!("Tone::rematch_key(): {} applied for {}", interval, key_type);
         let tonics: Vec<Tone> = tonics
             .into_iter()
-            .map(|e| e.add_interval(interval.clone()))
+            .filter_map(|e| e.add_interval(interval.clone()).ok())
             .collect();
 
         let mut matched_tonic;
@@ -332,8 +378,12 @@ impl Tone {
             | Interval::MajorSeventh => KeyType::Ionian,
             Interval::MinorSecond | Interval::MinorSixth => KeyType::Phrygian,
             Interval::MinorThird | Interval::MinorSeventh => KeyType::Dorian,
-            Interval::AugmentedFourth => KeyType::Lydian,
-            Interval::DiminishedFifth => KeyType::Locrian,
+            Interval::AugmentedSecond | Interval::AugmentedFourth | Interval::AugmentedFifth => {
+                KeyType::Lydian
+            }
+            Interval::DiminishedFourth
+            | Interval::DiminishedFifth
+            | Interval::DiminishedSeventh => KeyType::Locrian,
         };
         self.rematch_key(&key_type)
     }
@@ -368,9 +418,28 @@ impl Tone {
 
     pub(crate) fn rematch_chord(self, chord_type: &ChordType) -> Tone {
         let key_type = match chord_type {
-            ChordType::Major7 => KeyType::Ionian,
-            ChordType::Minor7 => KeyType::Dorian,
-            ChordType::Dominant7 => KeyType::Mixolydian,
+            ChordType::Major
+            | ChordType::Major7
+            | ChordType::Major6
+            | ChordType::Major9
+            | ChordType::Augmented
+            | ChordType::Augmented7
+            | ChordType::AugmentedMajor7 => KeyType::Ionian,
+            ChordType::Minor
+            | ChordType::Minor7
+            | ChordType::Minor6
+            | ChordType::Minor9
+            | ChordType::MinorMajor7 => KeyType::Dorian,
+            ChordType::Dominant7
+            | ChordType::Dominant9
+            | ChordType::Dominant11
+            | ChordType::Dominant13
+            | ChordType::Dominant7Sus4
+            | ChordType::Dominant7Flat9
+            | ChordType::Dominant7Sharp9
+            | ChordType::Dominant7Sharp11
+            | ChordType::Dominant7Flat13
+            | ChordType::Altered => KeyType::Mixolydian,
             ChordType::HalfDiminished7 => KeyType::Locrian,
             ChordType::Diminished7 => KeyType::Ionian,
         };
@@ -378,88 +447,102 @@ impl Tone {
     }
 
     fn tone_idx(tone: &NeutralTone, variant: &ToneVariant) -> i8 {
-        match tone {
-            NeutralTone::C => match variant {
-                ToneVariant::Flat => 12,
-                ToneVariant::Neutral => 1,
-                ToneVariant::Sharp => 2,
-            },
-            NeutralTone::D => match variant {
-                ToneVariant::Flat => 2,
-                ToneVariant::Neutral => 3,
-                ToneVariant::Sharp => 4,
-            },
-            NeutralTone::E => match variant {
-                ToneVariant::Flat => 4,
-                ToneVariant::Neutral => 5,
-                ToneVariant::Sharp => 6,
-            },
-            NeutralTone::F => match variant {
-                ToneVariant::Flat => 5,
-                ToneVariant::Neutral => 6,
-                ToneVariant::Sharp => 7,
-            },
-            NeutralTone::G => match variant {
-                ToneVariant::Flat => 7,
-                ToneVariant::Neutral => 8,
-                ToneVariant::Sharp => 9,
-            },
-            NeutralTone::A => match variant {
-                ToneVariant::Flat => 9,
-                ToneVariant::Neutral => 10,
-                ToneVariant::Sharp => 11,
-            },
-            NeutralTone::B => match variant {
-                ToneVariant::Flat => 11,
-                ToneVariant::Neutral => 12,
-                ToneVariant::Sharp => 1,
-            },
+        let alter = match variant {
+            ToneVariant::DoubleFlat => -2,
+            ToneVariant::Flat => -1,
+            ToneVariant::Neutral => 0,
+            ToneVariant::Sharp => 1,
+            ToneVariant::DoubleSharp => 2,
+        };
+        (tone.semitone() + alter).rem_euclid(12) + 1
+    }
+
+    /// Spell a pitch class (1-12) on the given letter, e.g. 8 on F is F##.
+    /// Returns `None` if that would take more than a double accidental.
+    fn spell(tone: NeutralTone, idx: i8) -> Option<Tone> {
+        // the alteration from the natural, folded into -6..=5
+        let alter = (idx - 1 - tone.semitone() + 6).rem_euclid(12) - 6;
+        let variant = match alter {
+            -2 => ToneVariant::DoubleFlat,
+            -1 => ToneVariant::Flat,
+            0 => ToneVariant::Neutral,
+            1 => ToneVariant::Sharp,
+            2 => ToneVariant::DoubleSharp,
+            _ => return None,
+        };
+        Some(Tone { idx, tone, variant })
+    }
+
+    /// Plain spelling of a pitch class: the natural if there is one, else the sharp.
+    pub(crate) fn from_idx(idx: i8) -> Tone {
+        let tones = gen_tones(idx);
+        tones
+            .iter()
+            .find(|e| e.variant == ToneVariant::Neutral)
+            .or_else(|| tones.iter().find(|e| e.variant == ToneVariant::Sharp))
+            .unwrap_or(&tones[0])
+            .clone()
+    }
+
+    /// Staff step of the letter within an octave, C = 0 .. B = 6.
+    pub(crate) fn letter_step(&self) -> i8 {
+        match self.tone {
+            NeutralTone::C => 0,
+            NeutralTone::D => 1,
+            NeutralTone::E => 2,
+            NeutralTone::F => 3,
+            NeutralTone::G => 4,
+            NeutralTone::A => 5,
+            NeutralTone::B => 6,
         }
     }
 
-    pub(crate) fn add_interval(&self, interval: Interval) -> Tone {
-        let mut idx = (self.idx + interval.key_diff()) % 12;
-        if idx == 0 {
-            idx = 12;
+    /// Semitones the accidental moves the letter by.
+    pub(crate) fn alter(&self) -> i8 {
+        match self.variant {
+            ToneVariant::DoubleFlat => -2,
+            ToneVariant::Flat => -1,
+            ToneVariant::Neutral => 0,
+            ToneVariant::Sharp => 1,
+            ToneVariant::DoubleSharp => 2,
         }
-        let target = self.tone.add_interval(&interval);
-        let matches = gen_tones(idx)
+    }
+
+    /// Other spellings of the same pitch class, e.g. `Db` for `C#`.
+    pub(crate) fn enharmonics(&self) -> Vec<Tone> {
+        gen_tones(self.idx)
             .into_iter()
-            .filter(|e| e.tone == target)
-            .collect::<Vec<Tone>>();
+            .filter(|e| e != self)
+            .collect()
+    }
+
+    /// The tone the interval above, spelled on the letter the interval asks
+    /// for (the major 7th of G# is F##). A target beyond a double accidental,
+    /// e.g. the augmented 5th of B##, is an error rather than another letter.
+    pub(crate) fn add_interval(&self, interval: Interval) -> Result<Tone, TheoryError> {
+        let idx = (self.idx - 1 + interval.key_diff()).rem_euclid(12) + 1;
+        let target = self.tone.add_interval(&interval);
+        let tone = Self::spell(target.clone(), idx);
 
         debug!(
-            "adding {:?}:{} to {}:{}, tgt {}, from {:?} found {:?}",
+            "adding {:?}:{} to {}:{}, tgt {}, found {:?}",
             interval,
             interval.key_diff(),
             self,
             self.idx,
             target,
-            gen_tones(idx),
-            matches,
+            tone,
         );
 
-        if matches.len() == 0 {
-            panic!("tone not available")
-        }
-        matches[0].clone()
+        tone.ok_or_else(|| TheoryError::Unspellable(format!("{} above {}", interval.name(), self)))
     }
 
-    fn minus_interval(&self, interval: Interval) -> Tone {
-        let mut idx = self.idx - interval.key_diff();
-        if idx < 0 {
-            idx += 12
-        };
-
+    /// The tone the interval below, spelled as `add_interval` spells it above.
+    pub(crate) fn minus_interval(&self, interval: Interval) -> Result<Tone, TheoryError> {
+        let idx = (self.idx - 1 - interval.key_diff()).rem_euclid(12) + 1;
         let target = self.tone.minus_interval(&interval);
-        let matches = gen_tones(idx)
-            .into_iter()
-            .filter(|e| e.tone == target)
-            .collect::<Vec<Tone>>();
-        if matches.len() == 0 {
-            panic!("tone not available")
-        }
-        matches[0].clone()
+        Self::spell(target, idx)
+            .ok_or_else(|| TheoryError::Unspellable(format!("{} below {}", interval.name(), self)))
     }
 }
 
@@ -475,157 +558,104 @@ impl fmt::Debug for Tone {
     }
 }
 
+/// Every spelling of a pitch class up to a double accidental, the fewest
+/// accidentals first; an idx outside 1-12 is folded back into it.
 fn gen_tones(idx: i8) -> Vec<Tone> {
-    match idx {
-        1 => {
-            vec![
-                Tone {
-                    idx: 1,
-                    tone: NeutralTone::B,
-                    variant: ToneVariant::Sharp,
-                },
-                Tone {
-                    idx: 1,
-                    tone: NeutralTone::C,
-                    variant: ToneVariant::Neutral,
-                },
-            ]
-        }
-        2 => {
-            vec![
-                Tone {
-                    idx: 2,
-                    tone: NeutralTone::C,
-                    variant: ToneVariant::Sharp,
-                },
-                Tone {
-                    idx: 2,
-                    tone: NeutralTone::D,
-                    variant: ToneVariant::Flat,
-                },
-            ]
-        }
-        3 => {
-            vec![Tone {
-                idx: 3,
-                tone: NeutralTone::D,
-                variant: ToneVariant::Neutral,
-            }]
-        }
-        4 => {
-            vec![
-                Tone {
-                    idx: 4,
-                    tone: NeutralTone::D,
-                    variant: ToneVariant::Sharp,
-                },
-                Tone {
-                    idx: 4,
-                    tone: NeutralTone::E,
-                    variant: ToneVariant::Flat,
-                },
-            ]
-        }
-        5 => {
-            vec![
-                Tone {
-                    idx: 5,
-                    tone: NeutralTone::E,
-                    variant: ToneVariant::Neutral,
-                },
-                Tone {
-                    idx: 5,
-                    tone: NeutralTone::F,
-                    variant: ToneVariant::Flat,
-                },
-            ]
-        }
-        6 => {
-            vec![
-                Tone {
-                    idx: 6,
-                    tone: NeutralTone::E,
-                    variant: ToneVariant::Sharp,
-                },
-                Tone {
-                    idx: 6,
-                    tone: NeutralTone::F,
-                    variant: ToneVariant::Neutral,
-                },
-            ]
-        }
-        7 => {
-            vec![
-                Tone {
-                    idx: 7,
-                    tone: NeutralTone::F,
-                    variant: ToneVariant::Sharp,
-                },
-                Tone {
-                    idx: 7,
-                    tone: NeutralTone::G,
-                    variant: ToneVariant::Flat,
-                },
-            ]
-        }
-        8 => {
-            vec![Tone {
-                idx: 8,
-                tone: NeutralTone::G,
-                variant: ToneVariant::Neutral,
-            }]
-        }
-        9 => {
-            vec![
-                Tone {
-                    idx: 9,
-                    tone: NeutralTone::G,
-                    variant: ToneVariant::Sharp,
-                },
-                Tone {
-                    idx: 9,
-                    tone: NeutralTone::A,
-                    variant: ToneVariant::Flat,
-                },
-            ]
-        }
-        10 => {
-            vec![Tone {
-                idx: 10,
-                tone: NeutralTone::A,
-                variant: ToneVariant::Neutral,
-            }]
+    let idx = (idx - 1).rem_euclid(12) + 1;
+    let mut tones: Vec<Tone> = NeutralTone::C
+        .derived_vec()
+        .into_iter()
+        .filter_map(|e| Tone::spell(e, idx))
+        .collect();
+    tones.sort_by_key(|e| e.alter().abs());
+    tones
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theory::dyad::Dyad;
+
+    fn tone(tone: NeutralTone, variant: ToneVariant) -> Tone {
+        Tone::new(tone, variant)
+    }
+
+    #[test]
+    fn intervals_are_spelled_on_the_letter_they_ask_for() {
+        let g_sharp = tone(NeutralTone::G, ToneVariant::Sharp);
+        assert_eq!(
+            g_sharp.add_interval(Interval::MajorSeventh),
+            Ok(tone(NeutralTone::F, ToneVariant::DoubleSharp))
+        );
+        let d_flat = tone(NeutralTone::D, ToneVariant::Flat);
+        assert_eq!(
+            d_flat.add_interval(Interval::DiminishedFifth),
+            Ok(tone(NeutralTone::A, ToneVariant::DoubleFlat))
+        );
+        let c = tone(NeutralTone::C, ToneVariant::Neutral);
+        assert_eq!(
+            c.add_interval(Interval::AugmentedFourth),
+            Ok(tone(NeutralTone::F, ToneVariant::Sharp))
+        );
+        assert_eq!(
+            c.minus_interval(Interval::MajorThird),
+            Ok(tone(NeutralTone::A, ToneVariant::Flat))
+        );
+    }
+
+    #[test]
+    fn intervals_beyond_a_double_accidental_are_unspellable() {
+        let c_flat = tone(NeutralTone::C, ToneVariant::Flat);
+        assert!(matches!(
+            c_flat.add_interval(Interval::DiminishedSeventh),
+            Err(TheoryError::Unspellable(_))
+        ));
+        let b_double_sharp = tone(NeutralTone::B, ToneVariant::DoubleSharp);
+        assert!(matches!(
+            b_double_sharp.add_interval(Interval::MajorSeventh),
+            Err(TheoryError::Unspellable(_))
+        ));
+    }
+
+    #[test]
+    fn minus_interval_undoes_add_interval() {
+        for idx in 1..=12 {
+            for from in gen_tones(idx) {
+                for interval in Dyad::interval_vec() {
+                    let Ok(above) = from.add_interval(interval.clone()) else {
+                        continue;
+                    };
+                    assert_eq!(above.minus_interval(interval), Ok(from.clone()));
+                }
+            }
         }
-        11 => {
+    }
+
+    #[test]
+    fn pitch_classes_spell_plainest_first() {
+        assert_eq!(
+            Tone::spell(NeutralTone::F, 8),
+            Some(tone(NeutralTone::F, ToneVariant::DoubleSharp))
+        );
+        assert_eq!(Tone::spell(NeutralTone::F, 10), None);
+        assert_eq!(
+            gen_tones(9),
             vec![
-                Tone {
-                    idx: 11,
-                    tone: NeutralTone::A,
-                    variant: ToneVariant::Sharp,
-                },
-                Tone {
-                    idx: 11,
-                    tone: NeutralTone::B,
-                    variant: ToneVariant::Flat,
-                },
+                tone(NeutralTone::G, ToneVariant::Sharp),
+                tone(NeutralTone::A, ToneVariant::Flat),
             ]
-        }
-        12 => {
+        );
+        assert_eq!(
+            Tone::from_idx(1),
+            tone(NeutralTone::C, ToneVariant::Neutral)
+        );
+        assert_eq!(Tone::from_idx(2), tone(NeutralTone::C, ToneVariant::Sharp));
+        assert_eq!(
+            tone(NeutralTone::C, ToneVariant::Sharp).enharmonics(),
             vec![
-                Tone {
-                    idx: 12,
-                    tone: NeutralTone::B,
-                    variant: ToneVariant::Neutral,
-                },
-                Tone {
-                    idx: 12,
-                    tone: NeutralTone::C,
-                    variant: ToneVariant::Flat,
-                },
+                tone(NeutralTone::D, ToneVariant::Flat),
+                tone(NeutralTone::B, ToneVariant::DoubleSharp),
             ]
-        }
-        _ => {
-            panic!("key idx not valid")
-        }
+        );
     }
 }
@@ -0,0 +1,73 @@
+use log::debug;
+
+use crate::app::Difficulty;
+use super::{
+    chord::{Chord, Voicing, VoicingType},
+    pitch::Pitch,
+};
+
+/// Whole compass of the instrument, used when no range is given.
+fn compass(difficulty: &Difficulty) -> (u8, u8) {
+    match difficulty {
+        Difficulty::Piano => (21, 108),
+        Difficulty::Guitar => (40, 88),
+    }
+}
+
+/// Total semitone motion between two voicings. Voicings with the same number
+/// of notes move voice by voice, otherwise every note moves to the closest
+/// note of the other voicing.
+pub(crate) fn motion(from: &[Pitch], to: &[Pitch]) -> u32 {
+    let distance = |a: &Pitch, b: &Pitch| (a.midi as i16 - b.midi as i16).unsigned_abs() as u32;
+    let closest =
+        |a: &Pitch, voicing: &[Pitch]| voicing.iter().map(|b| distance(a, b)).min().unwrap_or(0);
+    if from.len() == to.len() {
+        from.iter()
+            .zip(to.iter())
+            .map(|(a, b)| distance(a, b))
+            .sum()
+    } else {
+        from.iter().map(|a| closest(a, to)).sum::<u32>()
+            + to.iter().map(|b| closest(b, from)).sum::<u32>()
+    }
+}
+
+/// Voice the chord in the inversion of the voicing type and the register that
+/// moves the least from the previous chord, keeping every note inside the
+/// range (low, high).
+pub(crate) fn lead(
+    previous: &Chord,
+    chord: &Chord,
+    voicing_type: &VoicingType,
+    difficulty: Difficulty,
+    range: Option<(u8, u8)>,
+) -> Chord {
+    let (low, high) = range.unwrap_or_else(|| compass(&difficulty));
+    let mut best: Option<(u32, Chord)> = None;
+    for layout in Voicing::all_vec(voicing_type, &difficulty).into_iter() {
+        for floor in low..=high {
+            let Ok(candidate) = chord.revoiced(layout.clone(), floor) else {
+                continue;
+            };
+            // stacked from the floor, only the top can leave the range
+            match candidate.voicing.last() {
+                Some(top) if top.midi <= high => {}
+                _ => continue,
+            }
+            let cost = motion(&previous.voicing, &candidate.voicing);
+            if best.as_ref().is_none_or(|(min, _)| cost < *min) {
+                best = Some((cost, candidate));
+            }
+        }
+    }
+    match best {
+        Some((cost, led)) => {
+            debug!(
+                "voice_leading::lead(): {:?} -> {:?}, {} semitones",
+                previous.voicing, led.voicing, cost
+            );
+            led
+        }
+        None => chord.clone(),
+    }
+}
@@ -6,9 +6,14 @@ use ratatui::{
     Frame,
 };
 use crate::app::App;
+use super::strip_ansi;
 
-pub fn render(frame: &mut Frame, _app: &App, area: Rect) {
-    let line = Line::from(vec![
+pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+    let voice_leading = match app.voice_leading() {
+        true => " Voice leading: on  ",
+        false => " Voice leading: off  ",
+    };
+    let mut spans = vec![
         Span::styled(
             " [Enter]",
             Style::default()
@@ -16,6 +21,13 @@ pub fn render(frame: &mut Frame, _app: &App, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" Skip  "),
+        Span::styled(
+            "[V]",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(voice_leading),
         Span::styled(
             "[Q]",
             Style::default()
@@ -23,7 +35,21 @@ pub fn render(frame: &mut Frame, _app: &App, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" Quit"),
-    ]);
+    ];
+    if let Some(e) = &app.midi_error {
+        spans.push(Span::styled(
+            format!("  ! no MIDI connection: {}", e),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    // a failed step is recovered from, but still worth telling
+    if let Some(e) = &app.error {
+        spans.push(Span::styled(
+            format!("  ! {}", strip_ansi(&e.to_string())),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let line = Line::from(spans);
     let paragraph = Paragraph::new(line);
     frame.render_widget(paragraph, area);
 }
@@ -1,15 +1,151 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
-    widgets::{Block, Borders},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
     Frame,
 };
-use crate::app::App;
+use crate::app::{App, GamePhase};
+use crate::theory::{
+    chord::Chord,
+    fretboard::{Fingering, Tuning, MAX_STRETCH},
+    pitch::Pitch,
+};
+use crate::ui::strip_ansi;
+
+/// Frets shown to the right of the nut or of the hand position.
+const SHOWN_FRETS: u8 = 5;
+
+/// Chord to finger in the current game phase, with its highlight colour.
+fn target_chord(app: &App) -> Option<(&Chord, Color)> {
+    match &app.phase {
+        GamePhase::WaitingForInput { target, .. } => Some((target, Color::Yellow)),
+        GamePhase::Matched { chord, .. } => Some((chord, Color::Green)),
+        _ => None,
+    }
+}
+
+/// One string, highest first as on a tab: the open string name, whether it
+/// is played open or muted, then the frets from the first one shown.
+fn build_string_row(
+    fingering: &Fingering,
+    string: usize,
+    first: u8,
+    hi_color: Color,
+) -> Vec<Span<'static>> {
+    let open = fingering.tuning.strings()[string];
+    let fret = fingering.frets[string];
+    let tone = fingering
+        .pitches
+        .iter()
+        .find(|e| Some(e.midi) == fret.map(|f| open + f))
+        .map(|e| strip_ansi(&format!("{}", e.tone)))
+        .unwrap_or_default();
 
-pub fn render(frame: &mut Frame, _app: &App, area: Rect) {
+    let mut spans = vec![Span::styled(
+        format!(
+            "  {:>3} ",
+            strip_ansi(&format!("{}", Pitch::from_midi(open)))
+        ),
+        Style::default().fg(Color::DarkGray),
+    )];
+    let (mark, color) = match fret {
+        None => ("x".to_string(), Color::DarkGray),
+        Some(0) => (tone.clone(), hi_color),
+        Some(_) => (" ".to_string(), Color::White),
+    };
+    spans.push(Span::styled(
+        format!("{:>2}", mark),
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    ));
+    spans.push(Span::styled(
+        if first <= 1 { "\u{2016}" } else { "|" },
+        Style::default().fg(Color::White),
+    ));
+    for f in first..first + SHOWN_FRETS {
+        match fret {
+            Some(played) if played == f => {
+                spans.push(Span::styled(
+                    format!("{:-^4}", tone),
+                    Style::default().fg(hi_color).add_modifier(Modifier::BOLD),
+                ));
+            }
+            _ => {
+                spans.push(Span::styled(
+                    "----".to_string(),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+        }
+        spans.push(Span::styled("|", Style::default().fg(Color::White)));
+    }
+    spans
+}
+
+/// Fret numbers under the strings.
+fn build_fret_row(first: u8) -> Vec<Span<'static>> {
+    let mut spans = vec![Span::raw("         ")];
+    for f in first..first + SHOWN_FRETS {
+        spans.push(Span::styled(
+            format!("{:^5}", f),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    spans
+}
+
+pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
         .title(" Guitar ")
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray));
-    frame.render_widget(block, area);
+
+    let tuning = app.tuning().cloned().unwrap_or(Tuning::Standard);
+    let mut lines: Vec<Line<'static>> = vec![Line::from("")];
+    if let Some((chord, hi_color)) = target_chord(app) {
+        match Fingering::of(chord, &tuning) {
+            Some(fingering) => {
+                // the nut is shown while the hand fits in the first frets
+                let first = match fingering.position() {
+                    p if p + fingering.stretch() <= SHOWN_FRETS => 1,
+                    p => p,
+                };
+                for string in (0..fingering.frets.len()).rev() {
+                    lines.push(Line::from(build_string_row(
+                        &fingering, string, first, hi_color,
+                    )));
+                }
+                lines.push(Line::from(build_fret_row(first)));
+                let shape = match &fingering.shape {
+                    Some(shape) => format!("{} shape", strip_ansi(&format!("{}", shape))),
+                    None => "no CAGED shape".to_string(),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("  Fingering: ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!(
+                            "{}, fret {}, stretch {}",
+                            shape,
+                            fingering.position(),
+                            fingering.stretch()
+                        ),
+                        Style::default().fg(hi_color).add_modifier(Modifier::BOLD),
+                    ),
+                ]));
+            }
+            None => {
+                lines.push(Line::from(vec![Span::styled(
+                    format!(
+                        "  no fingering within {} frets in {} tuning",
+                        MAX_STRETCH,
+                        strip_ansi(&format!("{}", tuning))
+                    ),
+                    Style::default().fg(Color::Red),
+                )]));
+            }
+        }
+    }
+
+    let paragraph = Paragraph::new(lines).block(block);
+    frame.render_widget(paragraph, area);
 }
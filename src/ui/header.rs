@@ -6,6 +6,7 @@ use ratatui::{
     Frame,
 };
 use crate::app::App;
+use super::strip_ansi;
 
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let elapsed = app.elapsed_secs();
@@ -35,6 +36,12 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(Color::Green),
         ),
         Span::raw(" | "),
+        Span::styled("Voicing: ", Style::default().fg(Color::White)),
+        Span::styled(
+            strip_ansi(&format!("{}", app.voicing_type)),
+            Style::default().fg(Color::Magenta),
+        ),
+        Span::raw(" | "),
         Span::styled(
             format!("Measure: {}/100", app.measure_num),
             Style::default().fg(Color::DarkGray),
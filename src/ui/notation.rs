@@ -7,64 +7,120 @@ use ratatui::{
 };
 
 use crate::app::{App, GamePhase};
-use crate::theory::tone::Tone;
+use crate::theory::{key::Key, pitch::Pitch, scale::ScaleType, tone::Tone};
 use crate::ui::strip_ansi;
 
-/// Staff row indices (top to bottom, 0-based):
-///  0: space above line 5 (G5 — not used in our mapping)
-///  1: Line 5 (F5 — not used)
-///  2: space (E5 — not used)
-///  3: Line 4 (D5 — not used)
-///  4: space (C5 — not used)
-///  5: Line 3 (B4)
-///  6: space (A4)
-///  7: Line 2 (G4)
-///  8: space (F4)
-///  9: Line 1 (E4)
-/// 10: space (D4)
-/// 11: ledger line (C4)
-const TOTAL_ROWS: usize = 12;
-
-/// Map the first character of the tone's display string to a staff row.
-fn note_letter_to_row(letter: char) -> Option<usize> {
-    match letter {
-        'C' => Some(11),
-        'D' => Some(10),
-        'E' => Some(9),
-        'F' => Some(8),
-        'G' => Some(7),
-        'A' => Some(6),
-        'B' => Some(5),
-        _ => None,
-    }
+/// Staff steps are diatonic steps counted from C0 (see `Pitch::staff_step`),
+/// one row each, so C4 is 28, E4 is 30 and F5 is 38.
+const MIDDLE_C: i16 = 28;
+/// Lines of the bass staff (G2 B2 D3 F3 A3) and the treble staff (E4 G4 B4 D5 F5).
+const BASS_LINES: (i16, i16) = (18, 26);
+const TREBLE_LINES: (i16, i16) = (30, 38);
+
+/// Lowest steps the sharps (A4 - G5) and the flats (F4 - E5) of a key
+/// signature are written in on the treble staff, two octaves lower on the bass.
+const TREBLE_SHARPS: i16 = 33;
+const TREBLE_FLATS: i16 = 31;
+
+/// Whether a given step is a line of the grand staff (as opposed to a space or a ledger).
+fn is_staff_line(step: i16) -> bool {
+    let on_staff = (BASS_LINES.0..=BASS_LINES.1).contains(&step)
+        || (TREBLE_LINES.0..=TREBLE_LINES.1).contains(&step);
+    on_staff && step % 2 == 0
 }
 
-/// Whether a given row is a staff line (as opposed to a space or the ledger).
-fn is_staff_line(row: usize) -> bool {
-    matches!(row, 1 | 3 | 5 | 7 | 9)
+/// Whether a note on the step needs a ledger line (middle C, or above/below the staves).
+fn is_ledger_line(step: i16) -> bool {
+    step % 2 == 0
+        && !is_staff_line(step)
+        && (step == MIDDLE_C || !(BASS_LINES.0..=TREBLE_LINES.1).contains(&step))
 }
 
-/// Extract the tones to display from the current game phase.
-fn get_target_tones(app: &App) -> Vec<Tone> {
+/// Extract the pitches to display from the current game phase.
+fn get_target_pitches(app: &App) -> Vec<Pitch> {
     match &app.phase {
-        GamePhase::WaitingForInput { target } => target.tones.clone(),
-        GamePhase::Matched { chord } => chord.tones.clone(),
+        GamePhase::WaitingForInput { target, .. } => target.voicing.clone(),
+        GamePhase::Matched { chord, .. } => chord.voicing.clone(),
+        // one note at a time: the next one of the scale
+        GamePhase::PlayingScale { expected, step, .. } => {
+            expected.get(*step).cloned().into_iter().collect()
+        }
+        // nothing to read off the staff before the interval is played
+        GamePhase::PlayingInterval { answer, .. } => answer.clone(),
         _ => Vec::new(),
     }
 }
 
+/// Key signature of the notes shown: the key of the chords, or of the scale
+/// being played when it is a mode. Other scales, and keys that would need
+/// more than double accidentals, are written without one.
+fn get_signature(app: &App) -> Vec<Tone> {
+    match &app.phase {
+        GamePhase::PlayingScale { scale, .. } => match &scale.scale_type {
+            ScaleType::Mode(key_type) => Key::new(scale.tonic.clone(), key_type.clone())
+                .signature()
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        },
+        GamePhase::PlayingInterval { .. } => Vec::new(),
+        _ => app.next.key.signature().unwrap_or_default(),
+    }
+}
+
+/// Steps of the accidentals of the signature on both staves, with their signs.
+fn signature_steps(signature: &[Tone]) -> Vec<(i16, i16, &'static str)> {
+    signature
+        .iter()
+        .map(|e| {
+            let (lowest, sign) = match e.alter() > 0 {
+                true => (TREBLE_SHARPS, "\u{266F}"),
+                false => (TREBLE_FLATS, "\u{266D}"),
+            };
+            let step = (lowest..lowest + 7)
+                .find(|s| s % 7 == e.letter_step() as i16)
+                .unwrap_or(lowest);
+            (step, step - 14, sign)
+        })
+        .collect()
+}
+
 /// Determine the note colour based on game phase.
 fn note_color(app: &App) -> Color {
     match &app.phase {
-        GamePhase::WaitingForInput { .. } => Color::Yellow,
-        GamePhase::Matched { .. } => Color::Green,
+        GamePhase::WaitingForInput { .. } | GamePhase::PlayingScale { .. } => Color::Yellow,
+        GamePhase::Matched { .. } | GamePhase::PlayingInterval { .. } => Color::Green,
         _ => Color::White,
     }
 }
 
-/// Build the display label for a tone (e.g. "C", "F#", "Bb").
-fn tone_label(tone: &Tone) -> String {
-    strip_ansi(&format!("{}", tone))
+/// Build the display label for a pitch under the key signature (e.g. "C4",
+/// "F#3", "B\u{266E}4"): the accidental is left out when the signature already
+/// gives it, and a natural sign cancels a letter the signature alters.
+fn pitch_label(pitch: &Pitch, signature: &[Tone]) -> String {
+    let name = strip_ansi(&format!("{}", pitch.tone));
+    let (letter, accidental) = name.split_at(1);
+    let signed = signature
+        .iter()
+        .find(|e| e.letter_step() == pitch.tone.letter_step())
+        .map(|e| e.alter())
+        .unwrap_or(0);
+    let accidental = match pitch.tone.alter() {
+        alter if alter == signed => "",
+        0 => "\u{266E}",
+        _ => accidental,
+    };
+    format!("{}{}{}", letter, accidental, pitch.octave())
+}
+
+/// Highest step shown: the window of `rows` steps is centred on the notes,
+/// or on the treble staff when there are none.
+fn top_step(pitches: &[Pitch], rows: usize) -> i16 {
+    let steps: Vec<i16> = pitches.iter().map(|p| p.staff_step()).collect();
+    let centre = match (steps.iter().min(), steps.iter().max()) {
+        (Some(low), Some(high)) => (low + high + 1) / 2,
+        _ => (TREBLE_LINES.0 + TREBLE_LINES.1) / 2,
+    };
+    centre + rows as i16 / 2
 }
 
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
@@ -74,57 +130,124 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         .border_style(Style::default().fg(Color::DarkGray));
     let inner = block.inner(area);
 
-    let tones = get_target_tones(app);
+    let pitches = get_target_pitches(app);
     let color = note_color(app);
-
-    // Build a lookup: row -> label string for each tone present.
-    let mut row_labels: Vec<Option<String>> = vec![None; TOTAL_ROWS];
-    for tone in &tones {
-        let label = tone_label(tone);
-        if let Some(first_char) = label.chars().next() {
-            if let Some(row) = note_letter_to_row(first_char) {
-                row_labels[row] = Some(label);
-            }
-        }
-    }
-
-    // The usable width inside the border (in columns).
-    let width = inner.width as usize;
+    let signature = get_signature(app);
+    let signs = signature_steps(&signature);
+
+    // The usable size inside the border, the signature taking a column for
+    // every accidental at the start of the staff when it fits.
+    let margin = match signature.len() {
+        0 => 0,
+        n if 4 * n + 4 < inner.width as usize => 2 * n + 2,
+        _ => 0,
+    };
+    let width = inner.width as usize - margin;
+    let rows = inner.height as usize;
+    let top = top_step(&pitches, rows);
 
     let line_style = Style::default().fg(Color::DarkGray);
+    let sign_style = Style::default().fg(Color::White);
     let note_style = Style::default()
         .fg(color)
         .add_modifier(Modifier::BOLD);
 
-    let mut lines: Vec<Line<'static>> = Vec::with_capacity(TOTAL_ROWS);
-
-    for row in 0..TOTAL_ROWS {
-        let has_note = row_labels[row].is_some();
-        let label = row_labels[row].clone().unwrap_or_default();
-
-        let line = if row == 11 {
-            // Ledger line row (C4): only draw a short ledger line if note is present.
-            if has_note {
-                build_ledger_line(width, &label, note_style, line_style)
-            } else {
-                // Empty row below the staff.
-                Line::from(Span::styled(" ".repeat(width), Style::default()))
-            }
-        } else if is_staff_line(row) {
+    let mut lines: Vec<Line<'static>> = Vec::with_capacity(rows);
+
+    let bottom = top - rows as i16 + 1;
+    // Notes outside the window are listed on its edge row, together with the
+    // note that row would have shown.
+    let steps: Vec<i16> = pitches.iter().map(|p| p.staff_step()).collect();
+    let edge = |clipped: &dyn Fn(i16) -> bool, arrow: &str| -> String {
+        let labels: Vec<String> = pitches
+            .iter()
+            .filter(|p| clipped(p.staff_step()))
+            .map(|p| pitch_label(p, &signature))
+            .collect();
+        format!("{} {}", arrow, labels.join(" "))
+    };
+    let above = match steps.iter().any(|&s| s > top) {
+        true => Some(edge(&|s| s >= top, "\u{25B2}")),
+        false => None,
+    };
+    let below = match steps.iter().any(|&s| s < bottom) {
+        true => Some(edge(&|s| s <= bottom, "\u{25BC}")),
+        false => None,
+    };
+
+    for step in (bottom..=top).rev() {
+        let clipped = match step {
+            s if s == top => above.clone(),
+            s if s == bottom => below.clone(),
+            _ => None,
+        };
+        if let Some(label) = clipped {
+            let mut spans = vec![Span::raw(" ".repeat(margin))];
+            spans.extend(build_edge_row(width, &label, note_style).spans);
+            lines.push(Line::from(spans));
+            continue;
+        }
+        let mut spans = build_signature(step, margin, &signs, sign_style, line_style);
+
+        let row_label = pitches
+            .iter()
+            .find(|p| p.staff_step() == step)
+            .map(|p| pitch_label(p, &signature));
+        let has_note = row_label.is_some();
+        let label = row_label.unwrap_or_default();
+
+        let line = if is_staff_line(step) {
             // Full staff line.
             build_staff_line(width, has_note, &label, note_style, line_style)
+        } else if has_note && is_ledger_line(step) {
+            // Short ledger line through the note.
+            build_ledger_line(width, &label, note_style, line_style)
         } else {
             // Space row.
             build_space_row(width, has_note, &label, note_style)
         };
 
-        lines.push(line);
+        spans.extend(line.spans);
+        lines.push(Line::from(spans));
     }
 
     let paragraph = Paragraph::new(lines).block(block);
     frame.render_widget(paragraph, area);
 }
 
+/// Build the start of a row: the signs of the key signature written on the
+/// step, over the staff line when the step is one.
+fn build_signature(
+    step: i16,
+    margin: usize,
+    signs: &[(i16, i16, &'static str)],
+    sign_style: Style,
+    line_style: Style,
+) -> Vec<Span<'static>> {
+    if margin == 0 {
+        return Vec::new();
+    }
+    let (fill, fill_style) = match is_staff_line(step) {
+        true => ("\u{2501}", line_style),
+        false => (" ", Style::default()),
+    };
+    let mut spans = Vec::new();
+    for column in 0..margin {
+        let sign = match column % 2 {
+            1 => signs
+                .get(column / 2)
+                .filter(|(treble, bass, _)| *treble == step || *bass == step)
+                .map(|e| e.2),
+            _ => None,
+        };
+        spans.push(match sign {
+            Some(sign) => Span::styled(sign, sign_style),
+            None => Span::styled(fill, fill_style),
+        });
+    }
+    spans
+}
+
 /// Build a full staff line, optionally with a note marker in the centre.
 fn build_staff_line(
     width: usize,
@@ -198,7 +321,25 @@ fn build_space_row(
     ])
 }
 
-/// Build a ledger line row for C4.
+/// Build the row listing notes that fall outside the staff window, e.g. "▲ A4".
+fn build_edge_row(width: usize, label: &str, note_style: Style) -> Line<'static> {
+    let label_len = label.chars().count();
+    if label_len >= width {
+        return Line::from(Span::styled(label.to_string(), note_style));
+    }
+
+    let remaining = width - label_len;
+    let left = remaining / 2;
+    let right = remaining - left;
+
+    Line::from(vec![
+        Span::raw(" ".repeat(left)),
+        Span::styled(label.to_string(), note_style),
+        Span::raw(" ".repeat(right)),
+    ])
+}
+
+/// Build a ledger line row, e.g. for C4.
 fn build_ledger_line(
     width: usize,
     label: &str,
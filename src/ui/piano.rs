@@ -6,6 +6,7 @@ use ratatui::{
     Frame,
 };
 use crate::app::{App, GamePhase};
+use crate::theory::{pitch::Pitch, scale::Scale};
 use crate::ui::strip_ansi;
 
 /// White keys: (pitch class index, label)
@@ -31,38 +32,118 @@ const BLACK_KEY_SLOTS: [(Option<(i8, &str)>, ); 7] = [
     (None,),             // after B (gap to next octave)
 ];
 
-const NUM_OCTAVES: usize = 3;
+const MIN_OCTAVES: usize = 3;
 
-/// Collect the active pitch-class indices from the current game phase.
-fn active_indices(app: &App) -> Vec<i8> {
+/// Collect the active MIDI notes of the chord's voicing (or the scale notes
+/// still to play, or the interval once matched) from the current game phase.
+fn active_notes(app: &App) -> Vec<u8> {
     match &app.phase {
-        GamePhase::WaitingForInput { target } => target.tones.iter().map(|t| t.idx).collect(),
-        GamePhase::Matched { chord } => chord.tones.iter().map(|t| t.idx).collect(),
+        GamePhase::WaitingForInput { target, .. } => {
+            target.voicing.iter().map(|p| p.midi).collect()
+        }
+        GamePhase::Matched { chord, .. } => chord.voicing.iter().map(|p| p.midi).collect(),
+        GamePhase::PlayingScale { expected, step, .. } => {
+            expected.iter().skip(*step).map(|p| p.midi).collect()
+        }
+        GamePhase::PlayingInterval { answer, .. } => answer.iter().map(|p| p.midi).collect(),
         _ => Vec::new(),
     }
 }
 
+/// MIDI note of the leftmost C: the octave holding the lowest active note,
+/// or the one below middle C when nothing is active.
+fn first_c(active: &[u8]) -> u8 {
+    match active.iter().min() {
+        Some(lowest) => lowest - lowest % 12,
+        None => 48,
+    }
+}
+
+/// Octaves shown from the leftmost C, enough to hold the highest active note.
+fn octaves(first_c: u8, active: &[u8]) -> usize {
+    match active.iter().max() {
+        Some(highest) => MIN_OCTAVES.max(((highest - first_c) / 12) as usize + 1),
+        None => MIN_OCTAVES,
+    }
+}
+
+/// MIDI note of a key given the octave (0-based from the leftmost C) and pitch class.
+fn note_of(first_c: u8, oct: usize, idx: i8) -> u8 {
+    first_c + (oct * 12) as u8 + (idx - 1) as u8
+}
+
+/// Notes to highlight on the keyboard.
+struct Keys {
+    active: Vec<u8>,
+    played: Vec<u8>,
+    scale: Vec<i8>, // pitch classes of the scale to improvise with
+    hi_color: Color,
+    first_c: u8,
+    octaves: usize,
+}
+
+/// Colour of an unmarked key: the secondary colour when it is in the scale.
+fn key_color(note: u8, keys: &Keys, default: Color) -> Color {
+    match keys.scale.contains(&Pitch::class_of(note)) {
+        true => Color::Cyan,
+        false => default,
+    }
+}
+
+/// The scale suggested over the chord of the current game phase.
+fn chord_scale(app: &App) -> Option<&Scale> {
+    match &app.phase {
+        GamePhase::WaitingForInput { scale, .. } | GamePhase::Matched { scale, .. } => Some(scale),
+        _ => None,
+    }
+}
+
+/// Style of a key: the target as a star, a played note outside it as a dot.
+fn key_mark(note: u8, keys: &Keys) -> Option<(&'static str, Color)> {
+    let target = keys.active.contains(&note);
+    let played = keys.played.contains(&note);
+    match (target, played) {
+        (true, true) => Some(("\u{2605}", Color::Green)),
+        (true, false) => Some(("\u{2605}", keys.hi_color)),
+        (false, true) => Some(("\u{25CF}", Color::Red)),
+        (false, false) => None,
+    }
+}
+
 /// Pick the highlight colour based on game phase.
 fn active_color(app: &App) -> Color {
     match &app.phase {
         GamePhase::Matched { .. } => Color::Green,
+        GamePhase::PlayingScale { expected, step, .. } if *step == expected.len() => Color::Green,
+        GamePhase::PlayingInterval { .. } => Color::Green,
         _ => Color::Yellow,
     }
 }
 
-/// Get a human-readable list of the chord's tone names.
+/// Get a human-readable list of the chord's pitches, e.g. "C3 E3 G3 Bb3".
 fn get_tone_names(app: &App) -> String {
     match &app.phase {
-        GamePhase::WaitingForInput { target } => target
-            .tones
+        GamePhase::WaitingForInput { target, .. } => target
+            .voicing
             .iter()
-            .map(|t| strip_ansi(&format!("{}", t)))
+            .map(|p| strip_ansi(&format!("{}", p)))
             .collect::<Vec<_>>()
             .join(" "),
-        GamePhase::Matched { chord } => chord
-            .tones
+        GamePhase::Matched { chord, .. } => chord
+            .voicing
             .iter()
-            .map(|t| strip_ansi(&format!("{}", t)))
+            .map(|p| strip_ansi(&format!("{}", p)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        GamePhase::PlayingScale { expected, step, .. } => expected
+            .iter()
+            .skip(*step)
+            .map(|p| strip_ansi(&format!("{}", p)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        GamePhase::PlayingInterval { answer, .. } => answer
+            .iter()
+            .map(|p| strip_ansi(&format!("{}", p)))
             .collect::<Vec<_>>()
             .join(" "),
         _ => String::new(),
@@ -75,28 +156,30 @@ fn get_tone_names(app: &App) -> String {
 /// straddling the boundary between two white keys — specifically in the
 /// 4-char slot that starts 2 chars before the boundary.  Gaps (no black
 /// key) are filled with spaces.
-fn build_black_row(active: &[i8], hi_color: Color) -> Vec<Span<'static>> {
+fn build_black_row(keys: &Keys) -> Vec<Span<'static>> {
     let mut spans: Vec<Span<'static>> = Vec::new();
 
-    for oct in 0..NUM_OCTAVES {
+    for oct in 0..keys.octaves {
         // Leading 2-char pad (left half of the first white key)
         spans.push(Span::raw("  ".to_string()));
 
         for slot in &BLACK_KEY_SLOTS {
             match slot.0 {
                 Some((idx, label)) => {
-                    if active.contains(&idx) {
-                        spans.push(Span::styled(
-                            " \u{2605}  ".to_string(),
-                            Style::default()
-                                .fg(hi_color)
-                                .add_modifier(Modifier::BOLD),
-                        ));
-                    } else {
-                        spans.push(Span::styled(
-                            format!("{:^4}", label),
-                            Style::default().fg(Color::White),
-                        ));
+                    let note = note_of(keys.first_c, oct, idx);
+                    match key_mark(note, keys) {
+                        Some((mark, color)) => {
+                            spans.push(Span::styled(
+                                format!(" {}  ", mark),
+                                Style::default().fg(color).add_modifier(Modifier::BOLD),
+                            ));
+                        }
+                        None => {
+                            spans.push(Span::styled(
+                                format!("{:^4}", label),
+                                Style::default().fg(key_color(note, keys, Color::White)),
+                            ));
+                        }
                     }
                 }
                 None => {
@@ -112,28 +195,30 @@ fn build_black_row(active: &[i8], hi_color: Color) -> Vec<Span<'static>> {
     spans
 }
 
-/// Build the block-graphic row for black keys (solid blocks or stars).
-fn build_black_block_row(active: &[i8], hi_color: Color) -> Vec<Span<'static>> {
+/// Build the block-graphic row for black keys (solid blocks or marks).
+fn build_black_block_row(keys: &Keys) -> Vec<Span<'static>> {
     let mut spans: Vec<Span<'static>> = Vec::new();
 
-    for _oct in 0..NUM_OCTAVES {
+    for oct in 0..keys.octaves {
         spans.push(Span::raw("  ".to_string()));
 
         for slot in &BLACK_KEY_SLOTS {
             match slot.0 {
                 Some((idx, _label)) => {
-                    if active.contains(&idx) {
-                        spans.push(Span::styled(
-                            "\u{2588}\u{2605}\u{2605}\u{2588}".to_string(),
-                            Style::default()
-                                .fg(hi_color)
-                                .add_modifier(Modifier::BOLD),
-                        ));
-                    } else {
-                        spans.push(Span::styled(
-                            "\u{2588}\u{2588}\u{2588}\u{2588}".to_string(),
-                            Style::default().fg(Color::DarkGray),
-                        ));
+                    let note = note_of(keys.first_c, oct, idx);
+                    match key_mark(note, keys) {
+                        Some((mark, color)) => {
+                            spans.push(Span::styled(
+                                format!("\u{2588}{}{}\u{2588}", mark, mark),
+                                Style::default().fg(color).add_modifier(Modifier::BOLD),
+                            ));
+                        }
+                        None => {
+                            spans.push(Span::styled(
+                                "\u{2588}\u{2588}\u{2588}\u{2588}".to_string(),
+                                Style::default().fg(key_color(note, keys, Color::DarkGray)),
+                            ));
+                        }
                     }
                 }
                 None => {
@@ -148,29 +233,35 @@ fn build_black_block_row(active: &[i8], hi_color: Color) -> Vec<Span<'static>> {
     spans
 }
 
-/// Build the white-key label row across all octaves.
-fn build_white_row(active: &[i8], hi_color: Color) -> Vec<Span<'static>> {
+/// Build the white-key label row across all octaves; each C carries its octave number.
+fn build_white_row(keys: &Keys) -> Vec<Span<'static>> {
     let mut spans: Vec<Span<'static>> = Vec::new();
 
-    for oct in 0..NUM_OCTAVES {
-        for (i, &(idx, label)) in WHITE_KEYS.iter().enumerate() {
-            if active.contains(&idx) {
-                spans.push(Span::styled(
-                    format!(" \u{2605}  "),
-                    Style::default()
-                        .fg(hi_color)
-                        .add_modifier(Modifier::BOLD),
-                ));
-            } else {
-                spans.push(Span::styled(
-                    format!(" {}  ", label),
-                    Style::default().fg(Color::White),
-                ));
+    for oct in 0..keys.octaves {
+        for &(idx, label) in WHITE_KEYS.iter() {
+            let note = note_of(keys.first_c, oct, idx);
+            match key_mark(note, keys) {
+                Some((mark, color)) => {
+                    spans.push(Span::styled(
+                        format!(" {}  ", mark),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                None => {
+                    let label = match idx {
+                        1 => format!("{}{}", label, (note / 12) as i8 - 1),
+                        _ => label.to_string(),
+                    };
+                    spans.push(Span::styled(
+                        format!(" {:<3}", label),
+                        Style::default().fg(key_color(note, keys, Color::White)),
+                    ));
+                }
             }
         }
 
         // Octave separator
-        if oct < NUM_OCTAVES - 1 {
+        if oct < keys.octaves - 1 {
             spans.push(Span::styled(
                 "\u{2502}".to_string(),
                 Style::default().fg(Color::DarkGray),
@@ -182,9 +273,9 @@ fn build_white_row(active: &[i8], hi_color: Color) -> Vec<Span<'static>> {
 }
 
 /// Build a separator line that sits between the black and white key areas.
-fn build_separator_row() -> Vec<Span<'static>> {
+fn build_separator_row(keys: &Keys) -> Vec<Span<'static>> {
     let octave_width = WHITE_KEYS.len() * 4; // 28 chars per octave
-    let total = octave_width * NUM_OCTAVES + (NUM_OCTAVES - 1); // separators
+    let total = octave_width * keys.octaves + (keys.octaves - 1); // separators
     vec![Span::styled(
         "\u{2500}".repeat(total),
         Style::default().fg(Color::DarkGray),
@@ -192,17 +283,17 @@ fn build_separator_row() -> Vec<Span<'static>> {
 }
 
 /// Build a bottom border row for the white keys.
-fn build_bottom_row() -> Vec<Span<'static>> {
+fn build_bottom_row(keys: &Keys) -> Vec<Span<'static>> {
     let mut spans: Vec<Span<'static>> = Vec::new();
 
-    for oct in 0..NUM_OCTAVES {
+    for oct in 0..keys.octaves {
         for _i in 0..WHITE_KEYS.len() {
             spans.push(Span::styled(
                 "\u{2584}\u{2584}\u{2584}\u{2584}".to_string(),
                 Style::default().fg(Color::DarkGray),
             ));
         }
-        if oct < NUM_OCTAVES - 1 {
+        if oct < keys.octaves - 1 {
             spans.push(Span::styled(
                 "\u{2534}".to_string(),
                 Style::default().fg(Color::DarkGray),
@@ -214,32 +305,38 @@ fn build_bottom_row() -> Vec<Span<'static>> {
 }
 
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
-    let active = active_indices(app);
+    let active = active_notes(app);
     let hi_color = active_color(app);
     let tone_names = get_tone_names(app);
-
-    let mut lines: Vec<Line<'static>> = Vec::new();
-
-    // Line 0: empty top padding
-    lines.push(Line::from(""));
-
-    // Line 1: black key labels
-    lines.push(Line::from(build_black_row(&active, hi_color)));
-
-    // Line 2: black key blocks
-    lines.push(Line::from(build_black_block_row(&active, hi_color)));
-
-    // Line 3: separator
-    lines.push(Line::from(build_separator_row()));
-
-    // Line 4: white key labels
-    lines.push(Line::from(build_white_row(&active, hi_color)));
-
-    // Line 5: bottom edge
-    lines.push(Line::from(build_bottom_row()));
-
-    // Line 6: empty
-    lines.push(Line::from(""));
+    let scale = chord_scale(app);
+    let first_c = first_c(&active);
+    let keys = Keys {
+        first_c,
+        octaves: octaves(first_c, &active),
+        played: app.played.iter().map(|p| p.midi).collect(),
+        scale: scale
+            .map(|e| e.tones.iter().map(|t| t.idx).collect())
+            .unwrap_or_default(),
+        active,
+        hi_color,
+    };
+
+    let mut lines: Vec<Line<'static>> = vec![
+        // Line 0: empty top padding
+        Line::from(""),
+        // Line 1: black key labels
+        Line::from(build_black_row(&keys)),
+        // Line 2: black key blocks
+        Line::from(build_black_block_row(&keys)),
+        // Line 3: separator
+        Line::from(build_separator_row(&keys)),
+        // Line 4: white key labels
+        Line::from(build_white_row(&keys)),
+        // Line 5: bottom edge
+        Line::from(build_bottom_row(&keys)),
+        // Line 6: empty
+        Line::from(""),
+    ];
 
     // Line 7: tone names below the keyboard
     if !tone_names.is_empty() {
@@ -254,6 +351,22 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         ]));
     }
 
+    // Line 8: the scale to improvise with, in the secondary colour
+    if let Some(scale) = scale {
+        let tones: Vec<String> = scale
+            .tones
+            .iter()
+            .map(|t| strip_ansi(&format!("{}", t)))
+            .collect();
+        lines.push(Line::from(vec![
+            Span::styled("  Scale: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("{}  {}", strip_ansi(&format!("{}", scale)), tones.join(" ")),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]));
+    }
+
     let block = Block::default()
         .title(" Piano ")
         .borders(Borders::ALL)
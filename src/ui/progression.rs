@@ -5,11 +5,73 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
-use crate::app::App;
+use crate::app::{App, Drill};
+use crate::theory::{chord::Chord, dyad::IntervalStats, scale::Scale};
 use super::strip_ansi;
 
+/// The scale being practiced and its tones, in place of the chords.
+fn scale_lines(scale: &Scale) -> Vec<Line<'static>> {
+    let tones: Vec<String> = scale
+        .tones
+        .iter()
+        .map(|e| strip_ansi(&format!("{}", e)))
+        .collect();
+    vec![
+        Line::from(vec![
+            Span::styled("  Scale: ", Style::default().fg(Color::White)),
+            Span::styled(
+                strip_ansi(&format!("{}", scale)),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            format!("  {}", tones.join(" ")),
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        )]),
+    ]
+}
+
+/// Answers to each interval and direction asked so far, in place of the chords.
+fn interval_lines(stats: &IntervalStats) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(vec![Span::styled(
+        "  Intervals:",
+        Style::default().fg(Color::White),
+    )])];
+    for (interval, direction, tally) in stats.rows() {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<16}{:<7}", interval.name(), direction),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(format!("{}", tally), Style::default().fg(Color::White)),
+        ]));
+    }
+    lines
+}
+
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let mut lines: Vec<Line> = Vec::new();
+    let block = Block::default()
+        .title(" Progression ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    if let (Drill::Scale, Some(scale)) = (&app.drill, &app.scale) {
+        let paragraph = Paragraph::new(scale_lines(scale)).block(block);
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    if let Drill::Interval = app.drill {
+        let paragraph = Paragraph::new(interval_lines(&app.interval_stats)).block(block);
+        frame.render_widget(paragraph, area);
+        return;
+    }
 
     let current_key = strip_ansi(&format!("{}", app.current.key));
     let current_chord = if !app.current.chords.is_empty() {
@@ -17,7 +79,46 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     } else {
         String::new()
     };
-    let modulation = strip_ansi(&format!("{}", app.modulation));
+    // labels are given in playing order, chords are stored target first
+    let playing: Vec<Chord> = app.next.chords.iter().rev().cloned().collect();
+    let mut numerals = if !app.current.chords.is_empty() {
+        app.labels(&playing)
+    } else {
+        vec![String::new(); playing.len() + 1]
+    };
+    let current_numeral = numerals.remove(0);
+    numerals.reverse();
+    let mut scales: Vec<String> = if !app.current.chords.is_empty() {
+        match app.chord_scales(&playing) {
+            Ok(scales) => scales
+                .iter()
+                .map(|e| strip_ansi(&format!("{}", e)))
+                .collect(),
+            Err(_) => vec![String::new(); playing.len() + 1],
+        }
+    } else {
+        vec![String::new(); playing.len() + 1]
+    };
+    let current_scale = scales.remove(0);
+    scales.reverse();
+    let modulation = match app.drill {
+        Drill::Functional | Drill::Scale => strip_ansi(&format!("{}", app.modulation)),
+        Drill::Tonnetz => "(walk the tonnetz by P, L, R, S, N and H)".to_string(),
+        Drill::Cadence => match &app.cadence {
+            Some(cadence) => format!("{} cadence", strip_ansi(&format!("{}", cadence))),
+            None => String::new(),
+        },
+        Drill::Template => match &app.template {
+            Some(template) => format!(
+                "{} {}/{}",
+                strip_ansi(&format!("{}", template)),
+                app.phrase,
+                template.phrases().len()
+            ),
+            None => String::new(),
+        },
+        Drill::Interval => String::new(),
+    };
     let next_key = strip_ansi(&format!("{}", app.next.key));
 
     lines.push(Line::from(vec![
@@ -30,6 +131,14 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         ),
         Span::raw("  "),
         Span::styled(&*current_chord, Style::default().fg(Color::Green)),
+        Span::styled(
+            format!("  {}", current_numeral),
+            Style::default().fg(Color::Magenta),
+        ),
+        Span::styled(
+            format!("  {}", current_scale),
+            Style::default().fg(Color::DarkGray),
+        ),
     ]));
 
     lines.push(Line::from(vec![Span::styled(
@@ -37,6 +146,16 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         Style::default().fg(Color::Yellow),
     )]));
 
+    if let Some(pivot) = &app.pivot {
+        lines.push(Line::from(vec![
+            Span::styled("  pivot: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                strip_ansi(&format!("{}", pivot)),
+                Style::default().fg(Color::Magenta),
+            ),
+        ]));
+    }
+
     lines.push(Line::from(""));
 
     lines.push(Line::from(vec![
@@ -67,13 +186,17 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
                     .fg(Color::White)
                     .add_modifier(Modifier::BOLD),
             ),
+            Span::styled(
+                format!("  {}", numerals[i]),
+                Style::default().fg(Color::Magenta),
+            ),
+            Span::styled(
+                format!("  {}", scales[i]),
+                Style::default().fg(Color::DarkGray),
+            ),
         ]));
     }
 
-    let block = Block::default()
-        .title(" Progression ")
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
     let paragraph = Paragraph::new(lines).block(block);
     frame.render_widget(paragraph, area);
 }
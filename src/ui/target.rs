@@ -9,13 +9,57 @@ use crate::app::{App, GamePhase};
 use super::strip_ansi;
 
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+    let numeral = match &app.phase {
+        GamePhase::WaitingForInput { numeral, .. } | GamePhase::Matched { numeral, .. } => {
+            format!("  {}", numeral)
+        }
+        GamePhase::PlayingScale {
+            expected,
+            step,
+            miss,
+            ..
+        } => {
+            // the way up ends on the octave, the way down follows
+            let direction = match *step < expected.len().div_ceil(2) {
+                true => "up",
+                false => "down",
+            };
+            match miss {
+                Some(miss) => format!(
+                    "  {} {}/{}  {}",
+                    direction,
+                    step,
+                    expected.len(),
+                    strip_ansi(miss)
+                ),
+                None => format!("  {} {}/{}", direction, step, expected.len()),
+            }
+        }
+        GamePhase::PlayingInterval {
+            miss: Some(miss), ..
+        } => format!("  {}", strip_ansi(miss)),
+        _ => String::new(),
+    };
     let (label, label_color, chord_str) = match &app.phase {
-        GamePhase::WaitingForInput { target } => {
+        GamePhase::WaitingForInput { target, .. } => {
             (">> PLAY", Color::Yellow, strip_ansi(&format!("{}", target)))
         }
-        GamePhase::Matched { chord } => {
+        GamePhase::Matched { chord, .. } => {
             ("** MATCHED", Color::Green, strip_ansi(&format!("{}", chord)))
         }
+        GamePhase::PlayingScale {
+            scale,
+            expected,
+            step,
+            ..
+        } => match *step == expected.len() {
+            true => ("** PLAYED", Color::Green, strip_ansi(&format!("{}", scale))),
+            false => (">> PLAY", Color::Yellow, strip_ansi(&format!("{}", scale))),
+        },
+        GamePhase::PlayingInterval { dyad, answer, .. } => match answer.is_empty() {
+            true => (">> PLAY", Color::Yellow, strip_ansi(&format!("{}", dyad))),
+            false => ("** MATCHED", Color::Green, strip_ansi(&format!("{}", dyad))),
+        },
         GamePhase::MeasureTimeout => ("!! TIMEOUT", Color::Red, String::new()),
         GamePhase::GameTimeout => ("!! GAME OVER", Color::Red, String::new()),
         GamePhase::Score => ("   SCORE", Color::Blue, format!("{}", app.score)),
@@ -46,6 +90,7 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
                 .fg(Color::White)
                 .add_modifier(Modifier::BOLD),
         ),
+        Span::styled(numeral, Style::default().fg(Color::Magenta)),
     ]);
 
     let block = Block::default()